@@ -0,0 +1,478 @@
+use serde::{Deserialize, Serialize};
+use std::f32::consts::{PI, TAU};
+
+// CPU versions of the colour conversions in shader.wgsl, sdf_shader.wgsl and
+// sub_view_shader.wgsl. The constants are the same so results match the gpu,
+// except that the cube root here also handles negative values where wgsl's pow
+// returns nan.
+
+// The matrices of linear_srgb_to_oklab and oklab_to_linear_srgb, row by row.
+const RGB_TO_LMS: [[f32; 3]; 3] = [
+    [0.412_221_46, 0.536_332_55, 0.051_445_995],
+    [0.211_903_5, 0.680_699_5, 0.107_396_96],
+    [0.088_302_46, 0.281_718_85, 0.629_978_7],
+];
+const LMS_TO_OKLAB: [[f32; 3]; 3] = [
+    [0.210_454_26, 0.793_617_8, -0.004_072_047],
+    [1.977_998_5, -2.428_592_2, 0.450_593_7],
+    [0.025_904_037, 0.782_771_77, -0.808_675_77],
+];
+const OKLAB_TO_LMS: [[f32; 3]; 3] = [
+    [1.0, 0.396_337_78, 0.215_803_76],
+    [1.0, -0.105_561_346, -0.063_854_17],
+    [1.0, -0.089_484_18, -1.291_485_5],
+];
+const LMS_TO_RGB: [[f32; 3]; 3] = [
+    [4.076_741_7, -3.307_711_6, 0.230_969_94],
+    [-1.268_438, 2.609_757_4, -0.341_319_38],
+    [-0.004_196_086_3, -0.703_418_6, 1.707_614_7],
+];
+
+fn mul(matrix: [[f32; 3]; 3], v: [f32; 3]) -> [f32; 3] {
+    matrix.map(|row| row[0] * v[0] + row[1] * v[1] + row[2] * v[2])
+}
+
+/// Gamma encoded sRGB, what images and the surface store.
+#[repr(C)]
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq)]
+pub struct Srgb {
+    pub r: f32,
+    pub g: f32,
+    pub b: f32,
+}
+
+/// sRGB primaries without the transfer function, what shaders blend in.
+#[repr(C)]
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq)]
+pub struct LinearRgb {
+    pub r: f32,
+    pub g: f32,
+    pub b: f32,
+}
+
+#[repr(C)]
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq)]
+pub struct Oklab {
+    pub l: f32,
+    pub a: f32,
+    pub b: f32,
+}
+
+/// Polar Oklab, `h` is in radians in the range -pi..pi like the shaders' `atan2(b, a)`.
+#[repr(C)]
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq)]
+pub struct Oklch {
+    pub l: f32,
+    pub c: f32,
+    pub h: f32,
+}
+
+/// Which way around the hue circle to go when interpolating, same as css.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HueInterpolation {
+    Shorter,
+    Longer,
+    Increasing,
+    Decreasing,
+}
+
+impl Srgb {
+    pub const fn new(r: f32, g: f32, b: f32) -> Self {
+        Self { r, g, b }
+    }
+
+    pub fn from_u8(rgb: [u8; 3]) -> Self {
+        let [r, g, b] = rgb.map(|c| f32::from(c) / 255.0);
+
+        Self { r, g, b }
+    }
+
+    /// Rounds to the nearest value after clamping to 0..1.
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    pub fn to_u8(self) -> [u8; 3] {
+        [self.r, self.g, self.b].map(|c| (c.clamp(0.0, 1.0) * 255.0).round() as u8)
+    }
+}
+
+impl LinearRgb {
+    pub const fn new(r: f32, g: f32, b: f32) -> Self {
+        Self { r, g, b }
+    }
+
+    /// True if every channel is within 0..1, allowing for a little rounding error.
+    pub fn in_gamut(self) -> bool {
+        const EPSILON: f32 = 1e-4;
+
+        [self.r, self.g, self.b]
+            .iter()
+            .all(|c| (-EPSILON..=1.0 + EPSILON).contains(c))
+    }
+
+    pub fn clamp(self) -> Self {
+        Self {
+            r: self.r.clamp(0.0, 1.0),
+            g: self.g.clamp(0.0, 1.0),
+            b: self.b.clamp(0.0, 1.0),
+        }
+    }
+}
+
+impl Oklab {
+    pub const fn new(l: f32, a: f32, b: f32) -> Self {
+        Self { l, a, b }
+    }
+
+    pub fn lerp(self, other: Self, t: f32) -> Self {
+        Self {
+            l: self.l + (other.l - self.l) * t,
+            a: self.a + (other.a - self.a) * t,
+            b: self.b + (other.b - self.b) * t,
+        }
+    }
+}
+
+impl Oklch {
+    pub const fn new(l: f32, c: f32, h: f32) -> Self {
+        Self { l, c, h }
+    }
+
+    /// Keeps the lightness and chroma and replaces the hue, this is what the hue
+    /// excluding blur in shader.wgsl does with the blurred and unblurred samples.
+    pub fn with_hue(self, h: f32) -> Self {
+        Self { h, ..self }
+    }
+
+    /// Interpolates lightness and chroma linearly and the hue around the circle.
+    /// A colour without chroma has no meaningful hue so the other colour's is used.
+    pub fn lerp(self, other: Self, t: f32, hue: HueInterpolation) -> Self {
+        const ACHROMATIC: f32 = 1e-6;
+
+        let (from, to) = match (self.c <= ACHROMATIC, other.c <= ACHROMATIC) {
+            (true, false) => (other.h, other.h),
+            (false, true) => (self.h, self.h),
+            _ => (self.h, other.h),
+        };
+
+        let mut d = (to - from).rem_euclid(TAU);
+
+        match hue {
+            HueInterpolation::Shorter if d > PI => d -= TAU,
+            HueInterpolation::Longer if d > 0.0 && d < PI => d -= TAU,
+            HueInterpolation::Decreasing if d > 0.0 => d -= TAU,
+            _ => {}
+        }
+
+        let h = (from + d * t + PI).rem_euclid(TAU) - PI;
+
+        Self {
+            l: self.l + (other.l - self.l) * t,
+            c: self.c + (other.c - self.c) * t,
+            h,
+        }
+    }
+
+    /// Reduces chroma, keeping lightness and hue, until the colour fits in sRGB.
+    pub fn clip_to_gamut(self) -> Self {
+        let base = Self {
+            l: self.l.clamp(0.0, 1.0),
+            c: self.c.max(0.0),
+            ..self
+        };
+
+        if LinearRgb::from(base).in_gamut() {
+            return base;
+        }
+
+        let (mut low, mut high) = (0.0, base.c);
+
+        for _ in 0..24 {
+            let c = f32::midpoint(low, high);
+
+            if LinearRgb::from(Self { c, ..base }).in_gamut() {
+                low = c;
+            } else {
+                high = c;
+            }
+        }
+
+        Self { c: low, ..base }
+    }
+}
+
+impl From<Srgb> for LinearRgb {
+    fn from(value: Srgb) -> Self {
+        let decode = |c: f32| {
+            if c <= 0.040_45 {
+                c / 12.92
+            } else {
+                ((c + 0.055) / 1.055).powf(2.4)
+            }
+        };
+
+        Self {
+            r: decode(value.r),
+            g: decode(value.g),
+            b: decode(value.b),
+        }
+    }
+}
+
+impl From<LinearRgb> for Srgb {
+    fn from(value: LinearRgb) -> Self {
+        let encode = |c: f32| {
+            if c <= 0.003_130_8 {
+                c * 12.92
+            } else {
+                1.055 * c.powf(1.0 / 2.4) - 0.055
+            }
+        };
+
+        Self {
+            r: encode(value.r),
+            g: encode(value.g),
+            b: encode(value.b),
+        }
+    }
+}
+
+/// `linear_srgb_to_oklab` in the shaders.
+impl From<LinearRgb> for Oklab {
+    fn from(value: LinearRgb) -> Self {
+        let lms = mul(RGB_TO_LMS, [value.r, value.g, value.b]).map(f32::cbrt);
+        let [l, a, b] = mul(LMS_TO_OKLAB, lms);
+
+        Self { l, a, b }
+    }
+}
+
+/// `oklab_to_linear_srgb` in the shaders.
+impl From<Oklab> for LinearRgb {
+    fn from(value: Oklab) -> Self {
+        let lms = mul(OKLAB_TO_LMS, [value.l, value.a, value.b]).map(|c| c * c * c);
+        let [r, g, b] = mul(LMS_TO_RGB, lms);
+
+        Self { r, g, b }
+    }
+}
+
+/// `lab_to_lch` in the shaders.
+impl From<Oklab> for Oklch {
+    fn from(value: Oklab) -> Self {
+        Self {
+            l: value.l,
+            c: (value.a * value.a + value.b * value.b).sqrt(),
+            h: value.b.atan2(value.a),
+        }
+    }
+}
+
+/// `lch_to_lab` in the shaders.
+impl From<Oklch> for Oklab {
+    fn from(value: Oklch) -> Self {
+        let (sin, cos) = value.h.sin_cos();
+
+        Self {
+            l: value.l,
+            a: value.c * cos,
+            b: value.c * sin,
+        }
+    }
+}
+
+impl From<Srgb> for Oklab {
+    fn from(value: Srgb) -> Self {
+        LinearRgb::from(value).into()
+    }
+}
+
+impl From<Oklab> for Srgb {
+    fn from(value: Oklab) -> Self {
+        LinearRgb::from(value).into()
+    }
+}
+
+impl From<LinearRgb> for Oklch {
+    fn from(value: LinearRgb) -> Self {
+        Oklab::from(value).into()
+    }
+}
+
+impl From<Oklch> for LinearRgb {
+    fn from(value: Oklch) -> Self {
+        Oklab::from(value).into()
+    }
+}
+
+impl From<Srgb> for Oklch {
+    fn from(value: Srgb) -> Self {
+        Oklab::from(value).into()
+    }
+}
+
+impl From<Oklch> for Srgb {
+    fn from(value: Oklch) -> Self {
+        LinearRgb::from(value).into()
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::float_cmp)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: [f32; 3], expected: [f32; 3], tolerance: f32) {
+        assert!(
+            actual
+                .iter()
+                .zip(expected)
+                .all(|(a, e)| (a - e).abs() <= tolerance),
+            "{actual:?} != {expected:?}"
+        );
+    }
+
+    fn lab(value: Oklab) -> [f32; 3] {
+        [value.l, value.a, value.b]
+    }
+
+    fn srgb(value: Srgb) -> [f32; 3] {
+        [value.r, value.g, value.b]
+    }
+
+    #[test]
+    fn srgb_to_oklab_reference_values() {
+        // from the oklab reference implementation, also used by css colour 4
+        let references = [
+            (Srgb::new(1.0, 1.0, 1.0), [1.0, 0.0, 0.0]),
+            (Srgb::new(0.0, 0.0, 0.0), [0.0, 0.0, 0.0]),
+            (Srgb::new(1.0, 0.0, 0.0), [0.627_955, 0.224_863, 0.125_846]),
+            (Srgb::new(0.0, 1.0, 0.0), [0.866_440, -0.233_888, 0.179_498]),
+            (
+                Srgb::new(0.0, 0.0, 1.0),
+                [0.452_014, -0.032_457, -0.311_528],
+            ),
+        ];
+
+        for (color, expected) in references {
+            assert_close(lab(color.into()), expected, 1e-4);
+        }
+    }
+
+    #[test]
+    fn round_trips() {
+        let colors = [
+            Srgb::new(1.0, 1.0, 1.0),
+            Srgb::new(0.0, 0.0, 0.0),
+            Srgb::new(0.2, 0.5, 0.8),
+            Srgb::new(0.9, 0.1, 0.3),
+            Srgb::new(0.02, 0.03, 0.01),
+        ];
+
+        for color in colors {
+            assert_close(srgb(Srgb::from(LinearRgb::from(color))), srgb(color), 1e-5);
+            assert_close(srgb(Srgb::from(Oklab::from(color))), srgb(color), 1e-4);
+            assert_close(srgb(Srgb::from(Oklch::from(color))), srgb(color), 1e-4);
+        }
+
+        for c in 0..=255 {
+            assert_eq!(Srgb::from_u8([c, 0, 255]).to_u8(), [c, 0, 255]);
+        }
+    }
+
+    #[test]
+    fn clip_to_gamut() {
+        for i in 0..12u8 {
+            let h = f32::from(i) / 12.0 * TAU - PI;
+            let color = Oklch::new(0.7, 0.4, h);
+            let clipped = color.clip_to_gamut();
+
+            assert!(!LinearRgb::from(color).in_gamut());
+            assert!(LinearRgb::from(clipped).in_gamut());
+            assert!(clipped.c < color.c);
+            assert_eq!((clipped.l, clipped.h), (color.l, color.h));
+        }
+
+        let inside = Oklch::from(Srgb::new(0.4, 0.5, 0.6));
+
+        assert_eq!(inside.clip_to_gamut(), inside);
+    }
+
+    #[test]
+    fn hue_interpolation_across_pi() {
+        let from = Oklch::new(0.5, 0.1, 3.0);
+        let to = Oklch::new(0.5, 0.1, -3.0);
+        let hue = |interpolation, t| from.lerp(to, t, interpolation).h;
+
+        // the short way crosses pi, the long way goes through zero
+        assert!((hue(HueInterpolation::Shorter, 0.5).abs() - PI).abs() < 1e-5);
+        assert!(hue(HueInterpolation::Longer, 0.5).abs() < 1e-5);
+        assert!((hue(HueInterpolation::Increasing, 0.5).abs() - PI).abs() < 1e-5);
+        assert!(hue(HueInterpolation::Decreasing, 0.5).abs() < 1e-5);
+
+        for interpolation in [
+            HueInterpolation::Shorter,
+            HueInterpolation::Longer,
+            HueInterpolation::Increasing,
+            HueInterpolation::Decreasing,
+        ] {
+            assert!((hue(interpolation, 0.0) - 3.0).abs() < 1e-5);
+            assert!((hue(interpolation, 1.0) + 3.0).abs() < 1e-5);
+        }
+
+        // grey has no hue of its own
+        let grey = Oklch::new(0.5, 0.0, 0.0);
+
+        assert!((grey.lerp(to, 0.5, HueInterpolation::Shorter).h + 3.0).abs() < 1e-5);
+    }
+
+    /// The coefficients of a wgsl function, the numbers that are multiplied by something.
+    fn shader_coefficients(shader: &str, function: &str) -> Vec<f32> {
+        let start = shader
+            .find(&format!("fn {function}("))
+            .unwrap_or_else(|| panic!("{function} is not in the shader"));
+        let body = &shader[start..];
+        let body = &body[..body.find("\n}").expect("the function ends")];
+        let tokens: Vec<_> = body.split_whitespace().collect();
+
+        tokens
+            .windows(3)
+            .filter(|window| window[2] == "*")
+            .filter_map(|window| {
+                let value: f32 = window[1].parse().ok()?;
+
+                Some(if window[0] == "-" { -value } else { value })
+            })
+            .collect()
+    }
+
+    #[test]
+    fn constants_match_the_shaders() {
+        let to_oklab: Vec<f32> = RGB_TO_LMS
+            .iter()
+            .chain(&LMS_TO_OKLAB)
+            .flatten()
+            .copied()
+            .collect();
+        // the shaders add lightness without multiplying it by one
+        let from_oklab: Vec<f32> = OKLAB_TO_LMS
+            .iter()
+            .flat_map(|row| &row[1..])
+            .chain(LMS_TO_RGB.iter().flatten())
+            .copied()
+            .collect();
+
+        for shader in [
+            include_str!("shader.wgsl"),
+            include_str!("sdf_shader.wgsl"),
+            include_str!("sub_view_shader.wgsl"),
+        ] {
+            assert_eq!(
+                shader_coefficients(shader, "linear_srgb_to_oklab"),
+                to_oklab
+            );
+            assert_eq!(
+                shader_coefficients(shader, "oklab_to_linear_srgb"),
+                from_oklab
+            );
+        }
+    }
+}
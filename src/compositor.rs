@@ -1,11 +1,39 @@
+use crate::math::vector::Vec4;
+
 pub trait Viewport {
     fn set_resolution(&mut self, width: u32, height: u32);
     fn get_view(&self) -> wgpu::TextureView;
+    /// Records the passes that draw the viewport into its view.
+    fn render(&mut self, encoder: &mut wgpu::CommandEncoder);
+    /// Whether the viewport changed since its last `render` and has to be drawn again,
+    /// `Compositor::mark_dirty` does the same from outside.
+    fn is_dirty(&self) -> bool {
+        false
+    }
+}
+
+/// Identifies a viewport of a `Compositor`, ids of removed viewports are ignored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ViewportId(u64);
+
+/// Returned by `Compositor::add_viewport`, the id rearranges the viewport.
+pub struct ViewportHandle<V> {
+    pub id: ViewportId,
+    pub viewport: std::rc::Rc<std::cell::RefCell<V>>,
 }
 
 struct ViewportData {
+    id: ViewportId,
     viewport: std::rc::Rc<std::cell::RefCell<dyn Viewport>>,
-    rect: glm::Vec4,
+    /// Position and size as fractions of the target, from the bottom left.
+    rect: Vec4,
+    /// Drawn after the viewports with a lower z, on top of them.
+    z: i32,
+    visible: bool,
+    /// Rendered before the next composite even if `Viewport::is_dirty` says it is clean.
+    dirty: bool,
+    /// The size last passed to `Viewport::set_resolution`.
+    resolution: (u32, u32),
     rect_uniform: wgpu::Buffer,
     bind_group: wgpu::BindGroup,
 }
@@ -16,11 +44,16 @@ pub struct Compositor {
     render_pipeline: wgpu::RenderPipeline,
     sampler: wgpu::Sampler,
     bind_group_layout: wgpu::BindGroupLayout,
+    /// Sorted by z, the order they were added in for the same z.
     viewports: Vec<ViewportData>,
+    next_id: u64,
+    /// Size of the target in pixels.
+    resolution: (u32, u32),
 }
 
 impl Compositor {
-    pub fn new(device: wgpu::Device, queue: wgpu::Queue) -> Self {
+    /// `format` is the format of the views passed to `render`.
+    pub fn new(device: wgpu::Device, queue: wgpu::Queue, format: wgpu::TextureFormat) -> Self {
         let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
             label: Some("Viewport bind group layout"),
             entries: &[
@@ -101,7 +134,7 @@ impl Compositor {
                 entry_point: Some("fs_main"),
                 compilation_options: wgpu::PipelineCompilationOptions::default(),
                 targets: &[Some(wgpu::ColorTargetState {
-                    format: wgpu::TextureFormat::Rgba8UnormSrgb,
+                    format,
                     blend: Some(wgpu::BlendState::ALPHA_BLENDING),
                     write_mask: wgpu::ColorWrites::ALL,
                 })],
@@ -117,6 +150,8 @@ impl Compositor {
             sampler,
             bind_group_layout,
             viewports: Vec::new(),
+            next_id: 0,
+            resolution: (0, 0),
         }
     }
 
@@ -149,6 +184,8 @@ impl Compositor {
         })
     }
 
+    /// Renders the visible viewports that are dirty and then composites them all into
+    /// `view`, in one command buffer.
     pub fn render(&mut self, view: &wgpu::TextureView) {
         let command_encoder_descriptor = wgpu::CommandEncoderDescriptor {
             label: Some("Render Encoder"),
@@ -158,6 +195,15 @@ impl Compositor {
             .device
             .create_command_encoder(&command_encoder_descriptor);
 
+        for data in self.viewports.iter_mut().filter(|data| data.visible) {
+            let mut viewport = data.viewport.borrow_mut();
+
+            if data.dirty || viewport.is_dirty() {
+                viewport.render(&mut command_encoder);
+                data.dirty = false;
+            }
+        }
+
         let color_attachment = wgpu::RenderPassColorAttachment {
             view,
             resolve_target: None,
@@ -184,7 +230,7 @@ impl Compositor {
 
         render_pass.set_pipeline(&self.render_pipeline);
 
-        for ViewportData { bind_group, .. } in self.viewports.iter() {
+        for ViewportData { bind_group, .. } in self.viewports.iter().filter(|data| data.visible) {
             render_pass.set_bind_group(0, Some(bind_group), &[]);
             render_pass.draw(0..4, 0..1);
         }
@@ -194,36 +240,461 @@ impl Compositor {
         self.queue.submit([command_encoder.finish()]);
     }
 
-    pub fn add_viewport<F, V>(&mut self, f: F) -> std::rc::Rc<std::cell::RefCell<V>>
+    /// Adds a viewport on top of the others at `rect`, see `set_rect`.
+    pub fn add_viewport<F, V>(&mut self, rect: Vec4, f: F) -> ViewportHandle<V>
     where
         F: Fn(
             &wgpu::Device,
             &wgpu::Queue,
-            &wgpu::Buffer,
         ) -> (std::rc::Rc<std::cell::RefCell<V>>, wgpu::TextureView),
         V: Viewport + 'static,
     {
         let rect_uniform = self.device.create_buffer(&wgpu::BufferDescriptor {
-            label: Some("screen size uniform"),
-            size: glm::max(
-                self.device.limits().min_uniform_buffer_offset_alignment,
-                std::mem::size_of::<glm::Vec4>() as u32,
-            ) as u64,
+            label: Some("viewport rect uniform"),
+            size: u64::from(self.device.limits().min_uniform_buffer_offset_alignment)
+                .max(std::mem::size_of::<Vec4>() as u64),
             usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
             mapped_at_creation: false,
         });
 
-        let (viewport, view) = f(&self.device, &self.queue, &rect_uniform);
+        let (viewport, view) = f(&self.device, &self.queue);
 
         let bind_group = self.create_bind_group(&rect_uniform, &view);
 
+        let id = ViewportId(self.next_id);
+        self.next_id += 1;
+
         self.viewports.push(ViewportData {
+            id,
             viewport: viewport.clone(),
-            bind_group,
-            rect: glm::vec4(0.0, 0.0, 0.0, 0.0),
+            rect,
+            z: self.viewports.last().map_or(0, |data| data.z),
+            visible: true,
+            dirty: true,
+            resolution: (0, 0),
             rect_uniform,
+            bind_group,
+        });
+
+        self.set_rect(id, rect);
+
+        ViewportHandle { id, viewport }
+    }
+
+    /// The size of the target in pixels, the viewports are resized to keep their rects.
+    pub fn set_resolution(&mut self, width: u32, height: u32) {
+        self.resolution = (width, height);
+
+        let ids: Vec<_> = self.viewports.iter().map(|data| data.id).collect();
+
+        for id in ids {
+            self.update_resolution(id);
+        }
+    }
+
+    /// Moves and resizes a viewport, `rect` is `x, y, width, height` as fractions
+    /// of the target from its bottom left corner.
+    pub fn set_rect(&mut self, id: ViewportId, rect: Vec4) {
+        let Some(data) = self.viewports.iter_mut().find(|data| data.id == id) else {
+            return;
+        };
+
+        data.rect = rect;
+
+        self.queue.write_buffer(&data.rect_uniform, 0, unsafe {
+            crate::util::as_u8_slice(&rect)
         });
 
-        viewport
+        self.update_resolution(id);
+    }
+
+    /// Viewports with a higher z are drawn on top, ones with the same z in the order they were added.
+    pub fn set_z(&mut self, id: ViewportId, z: i32) {
+        let Some(data) = self.viewports.iter_mut().find(|data| data.id == id) else {
+            return;
+        };
+
+        data.z = z;
+
+        // ids grow as viewports are added, so ties keep the order they were added in
+        self.viewports.sort_by_key(|data| (data.z, data.id));
+    }
+
+    /// Renders the viewport again before the next composite.
+    pub fn mark_dirty(&mut self, id: ViewportId) {
+        if let Some(data) = self.viewports.iter_mut().find(|data| data.id == id) {
+            data.dirty = true;
+        }
+    }
+
+    /// Hidden viewports keep their place and size but are not drawn or rendered.
+    pub fn set_visible(&mut self, id: ViewportId, visible: bool) {
+        if let Some(data) = self.viewports.iter_mut().find(|data| data.id == id) {
+            data.visible = visible;
+        }
+    }
+
+    /// `false` when there was no viewport with the id.
+    pub fn remove(&mut self, id: ViewportId) -> bool {
+        let len = self.viewports.len();
+
+        self.viewports.retain(|data| data.id != id);
+
+        self.viewports.len() != len
+    }
+
+    /// Tells the viewport when the pixel size of its rect changed and binds its new view.
+    #[allow(
+        clippy::cast_possible_truncation,
+        clippy::cast_sign_loss,
+        clippy::cast_precision_loss
+    )]
+    fn update_resolution(&mut self, id: ViewportId) {
+        let Some(index) = self.viewports.iter().position(|data| data.id == id) else {
+            return;
+        };
+
+        let data = &self.viewports[index];
+        let resolution = (
+            (data.rect.z * self.resolution.0 as f32).round() as u32,
+            (data.rect.w * self.resolution.1 as f32).round() as u32,
+        );
+
+        // textures can not be empty, keep the old one until it is visible again
+        if resolution == data.resolution || resolution.0 == 0 || resolution.1 == 0 {
+            return;
+        }
+
+        data.viewport
+            .borrow_mut()
+            .set_resolution(resolution.0, resolution.1);
+
+        let view = data.viewport.borrow().get_view();
+        let bind_group = self.create_bind_group(&data.rect_uniform, &view);
+
+        let data = &mut self.viewports[index];
+        data.resolution = resolution;
+        data.bind_group = bind_group;
+        data.dirty = true;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    const FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8UnormSrgb;
+    const WIDTH: u32 = 64;
+
+    const RED: [u8; 4] = [255, 0, 0, 255];
+    const GREEN: [u8; 4] = [0, 255, 0, 255];
+    const BLUE: [u8; 4] = [0, 0, 255, 255];
+    const CLEAR: [u8; 4] = [0, 0, 0, 0];
+
+    /// Fills its texture with `color` and its top row with `top`,
+    /// counting how often it was rendered and resized.
+    struct Counter {
+        device: wgpu::Device,
+        queue: wgpu::Queue,
+        texture: wgpu::Texture,
+        color: [u8; 4],
+        top: [u8; 4],
+        renders: u32,
+        resizes: u32,
+        resolution: (u32, u32),
+        changed: bool,
+    }
+
+    impl Viewport for Counter {
+        fn set_resolution(&mut self, width: u32, height: u32) {
+            self.texture = create_texture(&self.device, width, height);
+            self.resolution = (width, height);
+            self.resizes += 1;
+        }
+
+        fn get_view(&self) -> wgpu::TextureView {
+            self.texture
+                .create_view(&wgpu::TextureViewDescriptor::default())
+        }
+
+        fn render(&mut self, _encoder: &mut wgpu::CommandEncoder) {
+            let (width, height) = (self.texture.width(), self.texture.height());
+            let pixels: Vec<u8> = (0..height)
+                .flat_map(|y| (0..width).map(move |_| y))
+                .flat_map(|y| if y == 0 { self.top } else { self.color })
+                .collect();
+
+            self.queue.write_texture(
+                self.texture.as_image_copy(),
+                &pixels,
+                wgpu::TexelCopyBufferLayout {
+                    offset: 0,
+                    bytes_per_row: Some(width * 4),
+                    rows_per_image: Some(height),
+                },
+                self.texture.size(),
+            );
+
+            self.renders += 1;
+        }
+
+        fn is_dirty(&self) -> bool {
+            self.changed
+        }
+    }
+
+    fn create_texture(device: &wgpu::Device, width: u32, height: u32) -> wgpu::Texture {
+        device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("compositor test texture"),
+            size: wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: FORMAT,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT
+                | wgpu::TextureUsages::TEXTURE_BINDING
+                | wgpu::TextureUsages::COPY_SRC
+                | wgpu::TextureUsages::COPY_DST,
+            view_formats: &[],
+        })
+    }
+
+    /// A compositor drawing into a `WIDTH` wide texture that can be read back.
+    struct Fixture {
+        device: wgpu::Device,
+        queue: wgpu::Queue,
+        compositor: Compositor,
+        target: wgpu::Texture,
+    }
+
+    impl Fixture {
+        fn new(height: u32) -> Option<Fixture> {
+            let Ok((_instance, device, queue)) = crate::util::get_gpu() else {
+                eprintln!("no gpu adapter, skipping");
+                return None;
+            };
+
+            let mut compositor = Compositor::new(device.clone(), queue.clone(), FORMAT);
+            compositor.set_resolution(WIDTH, height);
+
+            Some(Fixture {
+                target: create_texture(&device, WIDTH, height),
+                device,
+                queue,
+                compositor,
+            })
+        }
+
+        fn add(&mut self, rect: Vec4, color: [u8; 4], top: [u8; 4]) -> ViewportHandle<Counter> {
+            self.compositor.add_viewport(rect, |device, queue| {
+                let counter = Counter {
+                    device: device.clone(),
+                    queue: queue.clone(),
+                    texture: create_texture(device, 1, 1),
+                    color,
+                    top,
+                    renders: 0,
+                    resizes: 0,
+                    resolution: (0, 0),
+                    changed: false,
+                };
+                let view = counter.get_view();
+                (Rc::new(RefCell::new(counter)), view)
+            })
+        }
+
+        /// Composites into the target and copies it back to the cpu, top row first.
+        fn composite(&mut self) -> Vec<Vec<[u8; 4]>> {
+            self.compositor.render(
+                &self
+                    .target
+                    .create_view(&wgpu::TextureViewDescriptor::default()),
+            );
+
+            crate::util::read_texture(&self.device, &self.queue, &self.target)
+                .iter()
+                .map(|row| {
+                    row.chunks(4)
+                        .map(|pixel| pixel.try_into().expect("pixels are 4 bytes"))
+                        .collect()
+                })
+                .collect()
+        }
+    }
+
+    #[test]
+    fn renders_dirty_visible_viewports() {
+        let Some(mut fixture) = Fixture::new(1) else {
+            return;
+        };
+
+        let ViewportHandle { id, viewport } =
+            fixture.add(Vec4::new(0.0, 0.0, 0.5, 1.0), GREEN, GREEN);
+        let renders = || viewport.borrow().renders;
+
+        assert_eq!(viewport.borrow().resolution, (32, 1));
+
+        // new viewports are dirty
+        fixture.composite();
+        assert_eq!(renders(), 1);
+
+        fixture.composite();
+        assert_eq!(renders(), 1);
+
+        fixture.compositor.mark_dirty(id);
+        fixture.composite();
+        assert_eq!(renders(), 2);
+
+        viewport.borrow_mut().changed = true;
+        fixture.composite();
+        assert_eq!(renders(), 3);
+        viewport.borrow_mut().changed = false;
+
+        // hidden viewports stay dirty until they are shown
+        fixture.compositor.set_visible(id, false);
+        fixture.compositor.mark_dirty(id);
+        fixture.composite();
+        assert_eq!(renders(), 3);
+
+        fixture.compositor.set_visible(id, true);
+        fixture.composite();
+        assert_eq!(renders(), 4);
+
+        fixture.compositor.set_resolution(WIDTH * 2, 1);
+        assert_eq!(viewport.borrow().resolution, (64, 1));
+        fixture.composite();
+        assert_eq!(renders(), 5);
+    }
+
+    #[test]
+    fn viewports_are_drawn_upright() {
+        let Some(mut fixture) = Fixture::new(4) else {
+            return;
+        };
+
+        fixture.add(Vec4::new(0.0, 0.0, 0.5, 1.0), GREEN, RED);
+
+        let rows = fixture.composite();
+        assert_eq!(rows[0][0], RED);
+        assert!(rows[1..].iter().all(|row| row[0] == GREEN));
+        assert!(rows.iter().all(|row| row[WIDTH as usize - 1] == CLEAR));
+    }
+
+    #[test]
+    fn rects_start_at_the_bottom_left() {
+        let Some(mut fixture) = Fixture::new(4) else {
+            return;
+        };
+
+        fixture.add(Vec4::new(0.5, 0.0, 0.5, 0.5), GREEN, GREEN);
+
+        let rows = fixture.composite();
+        assert_eq!(rows[0][WIDTH as usize - 1], CLEAR);
+        assert_eq!(rows[3][WIDTH as usize - 1], GREEN);
+        assert_eq!(rows[3][0], CLEAR);
+    }
+
+    #[test]
+    fn set_z_breaks_ties_by_insertion_order() {
+        let Some(mut fixture) = Fixture::new(1) else {
+            return;
+        };
+
+        let full = Vec4::new(0.0, 0.0, 1.0, 1.0);
+        let red = fixture.add(full, RED, RED).id;
+        fixture.add(full, GREEN, GREEN);
+
+        assert_eq!(fixture.composite()[0][0], GREEN);
+
+        fixture.compositor.set_z(red, 1);
+        assert_eq!(fixture.composite()[0][0], RED);
+
+        // back among the same z, it goes back under the one added after it
+        fixture.compositor.set_z(red, 0);
+        assert_eq!(fixture.composite()[0][0], GREEN);
+
+        let blue = fixture.add(full, BLUE, BLUE).id;
+        fixture.compositor.set_z(blue, -1);
+        assert_eq!(fixture.composite()[0][0], GREEN);
+    }
+
+    #[test]
+    fn set_rect_resizes_only_when_the_pixel_size_changes() {
+        let Some(mut fixture) = Fixture::new(1) else {
+            return;
+        };
+
+        let ViewportHandle { id, viewport } =
+            fixture.add(Vec4::new(0.0, 0.0, 0.5, 1.0), GREEN, GREEN);
+        assert_eq!(viewport.borrow().resizes, 1);
+
+        fixture
+            .compositor
+            .set_rect(id, Vec4::new(0.5, 0.0, 0.5, 1.0));
+        assert_eq!(viewport.borrow().resizes, 1);
+
+        let rows = fixture.composite();
+        assert_eq!(rows[0][0], CLEAR);
+        assert_eq!(rows[0][WIDTH as usize - 1], GREEN);
+
+        fixture
+            .compositor
+            .set_rect(id, Vec4::new(0.0, 0.0, 1.0, 1.0));
+        assert_eq!(viewport.borrow().resizes, 2);
+        assert_eq!(viewport.borrow().resolution, (WIDTH, 1));
+
+        // empty rects keep the old texture
+        fixture
+            .compositor
+            .set_rect(id, Vec4::new(0.0, 0.0, 0.0, 1.0));
+        assert_eq!(viewport.borrow().resizes, 2);
+    }
+
+    #[test]
+    fn set_visible_hides_and_shows() {
+        let Some(mut fixture) = Fixture::new(1) else {
+            return;
+        };
+
+        let red = fixture.add(Vec4::new(0.0, 0.0, 1.0, 1.0), RED, RED).id;
+        let green = fixture.add(Vec4::new(0.0, 0.0, 0.5, 1.0), GREEN, GREEN).id;
+
+        fixture.compositor.set_visible(green, false);
+        assert_eq!(fixture.composite()[0][0], RED);
+
+        fixture.compositor.set_visible(red, false);
+        assert_eq!(fixture.composite()[0][0], CLEAR);
+
+        fixture.compositor.set_visible(green, true);
+        assert_eq!(fixture.composite()[0][0], GREEN);
+    }
+
+    #[test]
+    fn remove() {
+        let Some(mut fixture) = Fixture::new(1) else {
+            return;
+        };
+
+        let ViewportHandle { id, viewport } = fixture.add(Vec4::new(0.0, 0.0, 1.0, 1.0), RED, RED);
+        fixture.composite();
+
+        assert!(fixture.compositor.remove(id));
+        assert!(!fixture.compositor.remove(id));
+        assert_eq!(fixture.composite()[0][0], CLEAR);
+
+        // ids of removed viewports are ignored
+        fixture.compositor.mark_dirty(id);
+        fixture.compositor.set_z(id, 1);
+        fixture
+            .compositor
+            .set_rect(id, Vec4::new(0.0, 0.0, 0.5, 1.0));
+        fixture.composite();
+        assert_eq!(viewport.borrow().renders, 1);
     }
 }
@@ -3,13 +3,40 @@ struct Segment {
   b: vec2<f32>,
 }
 
+// one instance per glyph, outlines are in em units with y up
+struct Glyph {
+  bounds: vec4<f32>,
+  color: vec4<f32>,
+  // pen position in pixels, y up from the top of the text
+  position: vec2<f32>,
+  // pixels per em
+  size: f32,
+  first_segment: u32,
+  // range of contour_markers that belong to this glyph
+  contours: vec2<u32>,
+}
+
+struct View {
+  // pixels to clip space, 2 over the target size
+  scale: vec2<f32>,
+  // pan in pixels, applied after zoom
+  offset: vec2<f32>,
+  zoom: f32,
+  // how much of each glyph's outline is drawn, for animating it
+  render_percent: f32,
+}
+
 @group(0) @binding(0) var<storage, read> segments: array<Segment>;
+// the end of every contour in segments, exclusive
 @group(0) @binding(1) var<storage, read> contour_markers: array<u32>;
-@group(0) @binding(2) var<uniform> render_percent: f32;
+@group(0) @binding(2) var<uniform> view: View;
+@group(0) @binding(3) var<storage, read> glyphs: array<Glyph>;
 
 struct VertexOutput {
     @builtin(position) clip_position: vec4<f32>,
-    @location(0) color: vec3<f32>,
+    // position in the glyph's em square
+    @location(0) em: vec2<f32>,
+    @location(1) @interpolate(flat) glyph: u32,
 };
 
 fn sdf_segment(
@@ -24,79 +51,68 @@ fn sdf_segment(
   return length(pa - ba * h);
 }
 
-fn sdf(p: vec2<f32>) -> f32 {
+// signed distance to the glyph's outline, negative inside by the non zero rule
+fn sdf(p: vec2<f32>, glyph: Glyph) -> f32 {
   var d = 1e6;
-  var n = vec2(0.0);
-  var s = 0.0;
+  var winding = 0;
 
-  var i = 0u;
+  let total = contour_markers[glyph.contours.y - 1u] - glyph.first_segment;
+  let drawn = glyph.first_segment + u32(ceil(f32(total) * view.render_percent));
 
-  for (var j = 0u; j < arrayLength(&contour_markers); j++) {
-    var d_c = 1e6;
-    var n_c = vec2(0.0);
-    var s_c = 0.0;
+  var i = glyph.first_segment;
 
-    for (; i < arrayLength(&segments) && f32(i) < f32(arrayLength(&segments)) * render_percent; i++) {
+  for (var j = glyph.contours.x; j < glyph.contours.y; j++) {
+    for (; i < min(contour_markers[j], drawn); i++) {
       let a = segments[i].a;
       let b = segments[i].b;
 
-      let new_d = sdf_segment(p, a, b);
-      let new_n = (b - a).yx * vec2(1.0, -1.0);
-
-      if (abs(new_d) < abs(d_c)) {
-        d_c = new_d;
-        n_c = new_n;
-
-        s_c = sign(dot(n_c, p - a));
-      }
-
-      if (abs(abs(new_d) - abs(d_c)) <= 0.001) {
-        let s_old = sign(dot(n_c, p - a));
-        let s_new = sign(dot(new_n, p - a));
+      d = min(d, sdf_segment(p, a, b));
 
-        if (s_old != s_new) {
-          d_c = new_d;
-          n_c = normalize(n_c) + normalize(new_n);
+      // crossings of a ray going right from p
+      if ((a.y <= p.y) != (b.y <= p.y)) {
+        let t = (p.y - a.y) / (b.y - a.y);
 
-          s_c = sign(dot(n_c, p - a));
+        if (a.x + t * (b.x - a.x) > p.x) {
+          winding += select(-1, 1, b.y > a.y);
         }
-
       }
-
-      n_c += normalize(new_n) / new_d;
     }
 
-    if (d_c < d) {
-      d = d_c;
-      n = n_c;
-      s = s_c;
-    }
+    i = contour_markers[j];
   }
 
-
-  return d * s;
+  return select(d, -d, winding != 0);
 }
 
 @vertex
 fn vs_main(
-    @builtin(vertex_index) in_vertex_index: u32,
+    @builtin(vertex_index) vertex: u32,
+    @builtin(instance_index) instance: u32,
 ) -> VertexOutput {
-    var out: VertexOutput;
+    let glyph = glyphs[instance];
+
+    // a pixel of room for antialiasing at any zoom
+    let padding = 1.0 / (glyph.size * view.zoom);
+    let corner = vec2(f32(vertex & 1u), f32(vertex >> 1u));
+    let em = mix(glyph.bounds.xy - padding, glyph.bounds.zw + padding, corner);
 
-    let x: f32 = array<f32, 3>(1.0, 1.0, -3.0)[in_vertex_index];
-    let y: f32 = array<f32, 3>(-3.0, 1.0, 1.0)[in_vertex_index];
+    let pixel = (glyph.position + em * glyph.size) * view.zoom + view.offset;
 
-    out.clip_position = vec4<f32>(x, y, 0.0, 1.0);
-    out.color = vec3<f32>(x, y, 0.0);
+    var out: VertexOutput;
+    out.clip_position = vec4(vec2(-1.0, 1.0) + pixel * view.scale, 0.0, 1.0);
+    out.em = em;
+    out.glyph = instance;
     return out;
 }
 
 @fragment
 fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
-  let uv = in.color.xy;
-  let _k = contour_markers[0];
+  let glyph = glyphs[in.glyph];
+  let pixel = length(fwidth(in.em)) / sqrt(2.0);
+
+  // the distance in pixels keeps edges a pixel wide however far it is zoomed
+  let d = sdf(in.em, glyph) / pixel;
+  let coverage = clamp(0.5 - d, 0.0, 1.0);
 
-  let d = sdf(uv * 1000.0);
-  
-  return vec4(f32(d < 0.0));
+  return vec4(glyph.color.rgb, glyph.color.a * coverage);
 }
@@ -2,139 +2,121 @@
 
 mod app;
 mod app_structure;
+// only text uses the srgb half so far, the oklab half is the shaders' reference
+#[allow(dead_code)]
+mod color;
+mod compositor;
 mod imgui_platform_impl;
 mod math;
+mod model;
 mod render_pipeline;
 mod renderer_backend;
 mod shader;
 mod text;
 mod util;
 
+use crate::compositor::{Compositor, Viewport};
+use crate::math::vector::Vec4;
+use crate::text::renderer::TextRenderer;
 use crate::text::Line;
-use image::EncodableLayout;
-use wgpu::util::DeviceExt;
 
-struct DevEvents {
-    pipeline: wgpu::RenderPipeline,
-    #[allow(dead_code)]
-    rect_buffer: wgpu::Buffer,
-    text_bind_group: wgpu::BindGroup,
+/// Draws the text into its own texture for the compositor.
+struct TextViewport {
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+    text: TextRenderer,
+    view: wgpu::TextureView,
 }
 
-impl DevEvents {
-    #[allow(clippy::too_many_lines)]
-    fn new(gpu: &app::Gpu, window: &app::WindowBundle) -> DevEvents {
-        let module = &gpu
-            .device
-            .create_shader_module(wgpu::include_wgsl!("uv_tris.wgsl"));
-
-        let pipeline = render_pipeline::Builder::new()
-            .add_device(&gpu.device)
-            .add_label("text render pipeline")
-            .add_vertex(wgpu::VertexState {
-                module,
-                entry_point: Some("vs_main"),
-                compilation_options: wgpu::PipelineCompilationOptions::default(),
-                buffers: &[],
-            })
-            .add_primitive(wgpu::PrimitiveState {
-                topology: wgpu::PrimitiveTopology::TriangleStrip,
-                ..Default::default()
-            })
-            .add_fragment(wgpu::FragmentState {
-                module,
-                entry_point: Some("fs_main"),
-                compilation_options: wgpu::PipelineCompilationOptions::default(),
-                targets: &[Some(wgpu::ColorTargetState {
-                    format: window.config.format,
-                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
-                    write_mask: wgpu::ColorWrites::ALL,
-                })],
-            })
-            .build();
-
-        let texture = &gpu.device.create_texture(&wgpu::TextureDescriptor {
-            label: Some("font atlas"),
+impl TextViewport {
+    const FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8UnormSrgb;
+
+    fn new(device: &wgpu::Device, queue: &wgpu::Queue) -> TextViewport {
+        let mut text = TextRenderer::new(
+            device,
+            queue,
+            Self::FORMAT,
+            &text::ATLAS,
+            text::atlas::Format::R8,
+        );
+
+        text.queue_text(Line::new("'Zane Gant'").quads());
+
+        TextViewport {
+            device: device.clone(),
+            queue: queue.clone(),
+            text,
+            // replaced once the compositor knows the window size
+            view: create_view(device, 1, 1),
+        }
+    }
+}
+
+impl Viewport for TextViewport {
+    fn set_resolution(&mut self, width: u32, height: u32) {
+        self.view = create_view(&self.device, width, height);
+    }
+
+    fn get_view(&self) -> wgpu::TextureView {
+        self.view.clone()
+    }
+
+    fn render(&mut self, encoder: &mut wgpu::CommandEncoder) {
+        self.text.prepare(&self.device, &self.queue);
+
+        let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: &self.view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color::RED),
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            ..Default::default()
+        });
+
+        self.text.render(&mut pass);
+    }
+}
+
+fn create_view(device: &wgpu::Device, width: u32, height: u32) -> wgpu::TextureView {
+    device
+        .create_texture(&wgpu::TextureDescriptor {
+            label: Some("text viewport texture"),
             size: wgpu::Extent3d {
-                width: text::ATLAS.width(),
-                height: text::ATLAS.height(),
+                width,
+                height,
                 depth_or_array_layers: 1,
             },
             mip_level_count: 1,
             sample_count: 1,
             dimension: wgpu::TextureDimension::D2,
-            format: wgpu::TextureFormat::Rgba8UnormSrgb,
-            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
-            view_formats: &[wgpu::TextureFormat::Rgba8UnormSrgb],
-        });
+            format: TextViewport::FORMAT,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        })
+        .create_view(&wgpu::TextureViewDescriptor::default())
+}
 
-        gpu.queue.write_texture(
-            wgpu::TexelCopyTextureInfo {
-                texture,
-                mip_level: 0,
-                origin: wgpu::Origin3d::ZERO,
-                aspect: wgpu::TextureAspect::All,
-            },
-            text::ATLAS.as_bytes(),
-            wgpu::TexelCopyBufferLayout {
-                offset: 0,
-                bytes_per_row: Some(text::ATLAS.width() * 4),
-                rows_per_image: Some(text::ATLAS.width()),
-            },
-            wgpu::Extent3d {
-                width: text::ATLAS.width(),
-                height: text::ATLAS.height(),
-                depth_or_array_layers: 1,
-            },
-        );
+struct DevEvents {
+    compositor: Compositor,
+}
 
-        let sampler = &gpu.device.create_sampler(&wgpu::SamplerDescriptor {
-            label: Some("sampler linear"),
-            address_mode_u: wgpu::AddressMode::ClampToEdge,
-            address_mode_v: wgpu::AddressMode::ClampToEdge,
-            address_mode_w: wgpu::AddressMode::ClampToEdge,
-            mag_filter: wgpu::FilterMode::Linear,
-            min_filter: wgpu::FilterMode::Linear,
-            mipmap_filter: wgpu::FilterMode::Nearest,
-            ..Default::default()
-        });
+impl DevEvents {
+    fn new(gpu: &app::Gpu, window: &app::WindowBundle) -> DevEvents {
+        let mut compositor =
+            Compositor::new(gpu.device.clone(), gpu.queue.clone(), window.config.format);
 
-        let mut line = Line::new("'Zane Gant'");
-
-        let rect_buffer = gpu
-            .device
-            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
-                label: Some("text rect buffer"),
-                contents: line.as_bytes(),
-                usage: wgpu::BufferUsages::STORAGE,
-            });
-
-        let text_bind_group = gpu.device.create_bind_group(&wgpu::BindGroupDescriptor {
-            label: Some("text bind group"),
-            layout: &pipeline.get_bind_group_layout(0),
-            entries: &[
-                wgpu::BindGroupEntry {
-                    binding: 0,
-                    resource: wgpu::BindingResource::TextureView(
-                        &texture.create_view(&wgpu::TextureViewDescriptor::default()),
-                    ),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 1,
-                    resource: wgpu::BindingResource::Sampler(sampler),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 2,
-                    resource: rect_buffer.as_entire_binding(),
-                },
-            ],
+        compositor.add_viewport(Vec4::new(0.0, 0.0, 1.0, 1.0), |device, queue| {
+            let viewport = TextViewport::new(device, queue);
+            let view = viewport.get_view();
+            (std::rc::Rc::new(std::cell::RefCell::new(viewport)), view)
         });
 
-        DevEvents {
-            pipeline,
-            rect_buffer,
-            text_bind_group,
-        }
+        compositor.set_resolution(window.config.width, window.config.height);
+
+        DevEvents { compositor }
     }
 }
 
@@ -197,39 +179,22 @@ impl app::EventHandler for DevEvents {
                     .surface
                     .configure(&gpu.device, &window_bundle.config);
 
+                self.compositor.set_resolution(width, height);
+
                 // window_bundle.window.request_redraw();
             }
             winit::event::WindowEvent::RedrawRequested => {
-                let mut ce = gpu
-                    .device
-                    .create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
-
                 let surface_texture = window_bundle
                     .surface
                     .get_current_texture()
                     .expect("failed to get surface texture");
 
-                {
-                    let mut pass = ce.begin_render_pass(&wgpu::RenderPassDescriptor {
-                        color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                            view: &surface_texture
-                                .texture
-                                .create_view(&wgpu::TextureViewDescriptor::default()),
-                            resolve_target: None,
-                            ops: wgpu::Operations {
-                                load: wgpu::LoadOp::Clear(wgpu::Color::RED),
-                                store: wgpu::StoreOp::Store,
-                            },
-                        })],
-                        ..Default::default()
-                    });
-
-                    pass.set_pipeline(&self.pipeline);
-                    pass.set_bind_group(0, &self.text_bind_group, &[]);
-                    pass.draw(0..4, 0..11);
-                }
-
-                gpu.queue.submit([ce.finish()]);
+                self.compositor.render(
+                    &surface_texture
+                        .texture
+                        .create_view(&wgpu::TextureViewDescriptor::default()),
+                );
+
                 window_bundle.window.pre_present_notify();
                 surface_texture.present();
                 std::thread::sleep(std::time::Duration::from_secs_f32(1.0 / 30.0));
@@ -0,0 +1,480 @@
+use super::matrix::{Mat3, Mat4};
+use super::vector::{Vec2, Vec3, Vec4};
+use super::{Identity, Sqrt, Zero};
+use serde::{Deserialize, Serialize};
+use std::ops::{Add, Div, Mul, Sub};
+
+macro_rules! create_aabb {
+    ($name: ident, $vec: ident, $($fields: ident)+) => {
+        /// Axis aligned bounding box, `min` is inclusive and so is `max`.
+        #[repr(C)]
+        #[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq)]
+        pub struct $name<T = f32> {
+            pub min: $vec<T>,
+            pub max: $vec<T>,
+        }
+
+        impl<T> $name<T> {
+            #[inline(always)]
+            pub const fn new(min: $vec<T>, max: $vec<T>) -> Self {
+                Self { min, max }
+            }
+        }
+
+        impl<T: PartialOrd + Copy> $name<T> {
+            /// The box spanning both corners in any order.
+            #[inline(always)]
+            pub fn from_corners(a: $vec<T>, b: $vec<T>) -> Self {
+                Self {
+                    min: a.min(b),
+                    max: a.max(b),
+                }
+            }
+
+            /// The smallest box containing every point, `None` if there are none.
+            pub fn from_points(points: impl IntoIterator<Item = $vec<T>>) -> Option<Self> {
+                let mut points = points.into_iter();
+                let first = points.next()?;
+
+                Some(points.fold(Self::new(first, first), Self::expand_to))
+            }
+
+            /// A box is empty if `max` is less than `min` on any axis.
+            #[inline(always)]
+            pub fn is_empty(self) -> bool {
+                $(self.max.$fields < self.min.$fields)||+
+            }
+
+            #[inline(always)]
+            pub fn contains_point(self, point: $vec<T>) -> bool {
+                $(point.$fields >= self.min.$fields && point.$fields <= self.max.$fields)&&+
+            }
+
+            /// True if `other` lies entirely inside this box.
+            #[inline(always)]
+            pub fn contains(self, other: Self) -> bool {
+                self.contains_point(other.min) && self.contains_point(other.max)
+            }
+
+            /// True if the boxes overlap, touching edges count as overlapping.
+            #[inline(always)]
+            pub fn intersects(self, other: Self) -> bool {
+                $(self.min.$fields <= other.max.$fields && other.min.$fields <= self.max.$fields)&&+
+            }
+
+            #[inline(always)]
+            pub fn union(self, other: Self) -> Self {
+                Self {
+                    min: self.min.min(other.min),
+                    max: self.max.max(other.max),
+                }
+            }
+
+            /// The overlapping region, `None` if the boxes don't overlap.
+            #[inline(always)]
+            pub fn intersection(self, other: Self) -> Option<Self> {
+                let intersection = Self {
+                    min: self.min.max(other.min),
+                    max: self.max.min(other.max),
+                };
+
+                if intersection.is_empty() {
+                    None
+                } else {
+                    Some(intersection)
+                }
+            }
+
+            /// Grows the box to include `point`.
+            #[inline(always)]
+            pub fn expand_to(self, point: $vec<T>) -> Self {
+                Self {
+                    min: self.min.min(point),
+                    max: self.max.max(point),
+                }
+            }
+
+            /// The point in the box closest to `point`.
+            #[inline(always)]
+            pub fn closest_point(self, point: $vec<T>) -> $vec<T> {
+                point.clamp(self.min, self.max)
+            }
+        }
+
+        impl<T: Add<Output = T> + Sub<Output = T> + Div<Output = T> + Identity + Copy> $name<T> {
+            #[inline(always)]
+            pub fn size(self) -> $vec<T> {
+                self.max - self.min
+            }
+
+            #[inline(always)]
+            pub fn center(self) -> $vec<T> {
+                (self.min + self.max) / (T::IDENTITY + T::IDENTITY)
+            }
+        }
+
+        impl<T: Add<Output = T> + Sub<Output = T> + Mul<Output = T> + Zero + Sqrt + PartialOrd + Copy>
+            $name<T>
+        {
+            /// Distance from `point` to the box, zero inside.
+            #[inline(always)]
+            pub fn distance(self, point: $vec<T>) -> T {
+                (point - self.closest_point(point)).len()
+            }
+        }
+
+        impl $name {
+            /// Entry and exit distances along the ray in units of `dir`, or `None` if the ray
+            /// misses. The entry distance is negative when `origin` is inside the box.
+            pub fn ray(self, origin: $vec, dir: $vec) -> Option<(f32, f32)> {
+                let mut entry = f32::NEG_INFINITY;
+                let mut exit = f32::INFINITY;
+
+                $(
+                    if dir.$fields == 0.0 {
+                        if origin.$fields < self.min.$fields || origin.$fields > self.max.$fields {
+                            return None;
+                        }
+                    } else {
+                        let a = (self.min.$fields - origin.$fields) / dir.$fields;
+                        let b = (self.max.$fields - origin.$fields) / dir.$fields;
+
+                        entry = entry.max(a.min(b));
+                        exit = exit.min(a.max(b));
+                    }
+                )+
+
+                if entry > exit || exit < 0.0 {
+                    None
+                } else {
+                    Some((entry, exit))
+                }
+            }
+
+            /// Where the segment from `a` to `b` enters and exits the box, as fractions of the
+            /// segment clamped to 0..1, or `None` if it doesn't touch the box.
+            pub fn segment(self, a: $vec, b: $vec) -> Option<(f32, f32)> {
+                let (entry, exit) = self.ray(a, b - a)?;
+
+                if entry > 1.0 {
+                    None
+                } else {
+                    Some((entry.max(0.0), exit.min(1.0)))
+                }
+            }
+        }
+    };
+}
+
+create_aabb!(Aabb2, Vec2, x y);
+create_aabb!(Aabb3, Vec3, x y z);
+
+impl Aabb2 {
+    pub fn corners(self) -> [Vec2; 4] {
+        [
+            self.min,
+            Vec2::new(self.max.x, self.min.y),
+            self.max,
+            Vec2::new(self.min.x, self.max.y),
+        ]
+    }
+
+    /// Bounds of the box after an affine 2d transform, `matrix` is applied to `(x, y, 1)`.
+    pub fn transform(self, matrix: Mat3) -> Self {
+        Self::from_points(self.corners().map(|c| (matrix * c.extend(1.0)).truncate()))
+            .expect("a box always has corners")
+    }
+}
+
+impl Aabb3 {
+    pub fn corners(self) -> [Vec3; 8] {
+        let (min, max) = (self.min, self.max);
+
+        [
+            min,
+            Vec3::new(max.x, min.y, min.z),
+            Vec3::new(min.x, max.y, min.z),
+            Vec3::new(max.x, max.y, min.z),
+            Vec3::new(min.x, min.y, max.z),
+            Vec3::new(max.x, min.y, max.z),
+            Vec3::new(min.x, max.y, max.z),
+            max,
+        ]
+    }
+
+    /// Bounds of the box after a transform, `matrix` is applied to `(x, y, z, 1)`
+    /// and the result is divided by w.
+    pub fn transform(self, matrix: Mat4) -> Self {
+        Self::from_points(self.corners().map(|c| {
+            let Vec4 { x, y, z, w } = matrix * c.extend(1.0);
+            Vec3::new(x, y, z) / w
+        }))
+        .expect("a box always has corners")
+    }
+}
+
+/// A rectangle rotated about its center.
+#[repr(C)]
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq)]
+pub struct OrientedRect {
+    pub center: Vec2,
+    pub half_size: Vec2,
+    /// Counter clockwise rotation in radians.
+    pub angle: f32,
+}
+
+impl OrientedRect {
+    pub const fn new(center: Vec2, half_size: Vec2, angle: f32) -> Self {
+        Self {
+            center,
+            half_size,
+            angle,
+        }
+    }
+
+    pub fn from_aabb(aabb: Aabb2, angle: f32) -> Self {
+        Self {
+            center: aabb.center(),
+            half_size: aabb.size() / 2.0,
+            angle,
+        }
+    }
+
+    /// The local x and y axes of the rect.
+    pub fn axes(self) -> [Vec2; 2] {
+        let (sin, cos) = self.angle.sin_cos();
+
+        [Vec2::new(cos, sin), Vec2::new(-sin, cos)]
+    }
+
+    /// `point` in the rect's local space, where the rect is centered at the origin.
+    pub fn to_local(self, point: Vec2) -> Vec2 {
+        let [x, y] = self.axes();
+        let d = point - self.center;
+
+        Vec2::new(d.dot(x), d.dot(y))
+    }
+
+    pub fn corners(self) -> [Vec2; 4] {
+        let [x, y] = self.axes();
+        let (x, y) = (x * self.half_size.x, y * self.half_size.y);
+
+        [
+            self.center - x - y,
+            self.center + x - y,
+            self.center + x + y,
+            self.center - x + y,
+        ]
+    }
+
+    pub fn bounds(self) -> Aabb2 {
+        Aabb2::from_points(self.corners()).expect("a rect always has corners")
+    }
+
+    pub fn contains_point(self, point: Vec2) -> bool {
+        let local = self.to_local(point).abs();
+
+        local.x <= self.half_size.x && local.y <= self.half_size.y
+    }
+
+    /// Signed distance to the rect, negative inside.
+    pub fn distance(self, point: Vec2) -> f32 {
+        self.to_local(point).rect(Vec2::ZERO, self.half_size)
+    }
+
+    /// Separating axis test, touching edges count as overlapping.
+    pub fn intersects(self, other: Self) -> bool {
+        let (a, b) = (self.corners(), other.corners());
+
+        self.axes().into_iter().chain(other.axes()).all(|axis| {
+            let project = |corners: [Vec2; 4]| {
+                corners
+                    .map(|c| c.dot(axis))
+                    .into_iter()
+                    .fold((f32::INFINITY, f32::NEG_INFINITY), |(min, max), d| {
+                        (min.min(d), max.max(d))
+                    })
+            };
+
+            let ((a_min, a_max), (b_min, b_max)) = (project(a), project(b));
+
+            a_min <= b_max && b_min <= a_max
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f32::consts::{FRAC_PI_2, FRAC_PI_4, SQRT_2};
+
+    fn assert_close(actual: Vec2, expected: Vec2) {
+        assert!(
+            (actual - expected).len() < 1e-5,
+            "{actual:?} != {expected:?}"
+        );
+    }
+
+    fn unit() -> Aabb2 {
+        Aabb2::new(Vec2::new(0.0, 0.0), Vec2::new(1.0, 1.0))
+    }
+
+    #[test]
+    fn union_and_intersection() {
+        let a = unit();
+        let b = Aabb2::new(Vec2::new(0.5, -1.0), Vec2::new(2.0, 0.5));
+
+        assert_eq!(
+            a.union(b),
+            Aabb2::new(Vec2::new(0.0, -1.0), Vec2::new(2.0, 1.0))
+        );
+        assert_eq!(
+            a.intersection(b),
+            Some(Aabb2::new(Vec2::new(0.5, 0.0), Vec2::new(1.0, 0.5)))
+        );
+        assert_eq!(a.intersection(b), b.intersection(a));
+
+        // touching edges overlap in a line
+        let right = Aabb2::new(Vec2::new(1.0, 0.0), Vec2::new(2.0, 1.0));
+        assert!(a.intersects(right));
+        assert_eq!(
+            a.intersection(right),
+            Some(Aabb2::new(Vec2::new(1.0, 0.0), Vec2::new(1.0, 1.0)))
+        );
+
+        let apart = Aabb2::new(Vec2::new(3.0, 3.0), Vec2::new(4.0, 4.0));
+        assert!(!a.intersects(apart));
+        assert_eq!(a.intersection(apart), None);
+
+        let c = Aabb3::new(Vec3::new(0.0, 0.0, 0.0), Vec3::new(2.0, 2.0, 2.0));
+        let d = Aabb3::new(Vec3::new(1.0, 1.0, 3.0), Vec3::new(3.0, 3.0, 4.0));
+        assert_eq!(
+            c.union(d),
+            Aabb3::new(Vec3::new(0.0, 0.0, 0.0), Vec3::new(3.0, 3.0, 4.0))
+        );
+        assert_eq!(c.intersection(d), None);
+    }
+
+    #[test]
+    fn contains() {
+        let a = unit();
+
+        assert!(a.contains_point(Vec2::new(0.5, 0.5)));
+        // both ends are inclusive
+        assert!(a.contains_point(Vec2::new(0.0, 1.0)));
+        assert!(!a.contains_point(Vec2::new(1.5, 0.5)));
+        assert!(a.contains(Aabb2::new(Vec2::new(0.25, 0.25), Vec2::new(0.75, 1.0))));
+        assert!(a.contains(a));
+        assert!(!a.contains(Aabb2::new(Vec2::new(0.5, 0.5), Vec2::new(1.5, 0.75))));
+    }
+
+    #[test]
+    fn from_points() {
+        let points = [
+            Vec2::new(1.0, 2.0),
+            Vec2::new(-1.0, 5.0),
+            Vec2::new(3.0, 0.0),
+        ];
+
+        assert_eq!(
+            Aabb2::from_points(points),
+            Some(Aabb2::new(Vec2::new(-1.0, 0.0), Vec2::new(3.0, 5.0)))
+        );
+        assert_eq!(Aabb2::<f32>::from_points([]), None);
+        assert_eq!(
+            Aabb2::from_corners(Vec2::new(1.0, 0.0), Vec2::new(0.0, 1.0)),
+            unit()
+        );
+        assert!(Aabb2::new(Vec2::new(1.0, 0.0), Vec2::new(0.0, 1.0)).is_empty());
+    }
+
+    #[test]
+    fn ray_and_segment() {
+        let a = unit();
+
+        assert_eq!(
+            a.ray(Vec2::new(-1.0, 0.5), Vec2::new(1.0, 0.0)),
+            Some((1.0, 2.0))
+        );
+        assert_eq!(
+            a.ray(Vec2::new(0.5, 0.5), Vec2::new(0.0, 1.0)),
+            Some((-0.5, 0.5))
+        );
+        assert_eq!(a.ray(Vec2::new(-1.0, 2.0), Vec2::new(1.0, 0.0)), None);
+        assert_eq!(a.ray(Vec2::new(2.0, 0.5), Vec2::new(1.0, 0.0)), None);
+
+        assert_eq!(
+            a.segment(Vec2::new(-1.0, 0.5), Vec2::new(3.0, 0.5)),
+            Some((0.25, 0.5))
+        );
+        assert_eq!(a.segment(Vec2::new(-2.0, 0.5), Vec2::new(-1.0, 0.5)), None);
+    }
+
+    #[test]
+    fn transform() {
+        let rotation = Mat3::from_axis_angle(Vec3::new(0.0, 0.0, 1.0), FRAC_PI_2);
+        let rotated = unit().transform(rotation);
+
+        assert_close(rotated.min, Vec2::new(-1.0, 0.0));
+        assert_close(rotated.max, Vec2::new(0.0, 1.0));
+    }
+
+    #[test]
+    fn oriented_rect_corners() {
+        let rect = OrientedRect::new(Vec2::new(1.0, 2.0), Vec2::new(2.0, 1.0), 0.0);
+        assert_eq!(
+            rect.corners(),
+            [
+                Vec2::new(-1.0, 1.0),
+                Vec2::new(3.0, 1.0),
+                Vec2::new(3.0, 3.0),
+                Vec2::new(-1.0, 3.0),
+            ]
+        );
+
+        // a quarter turn counter clockwise swaps the extents
+        let rect = OrientedRect::new(Vec2::new(1.0, 2.0), Vec2::new(2.0, 1.0), FRAC_PI_2);
+        let expected = [
+            Vec2::new(2.0, 0.0),
+            Vec2::new(2.0, 4.0),
+            Vec2::new(0.0, 4.0),
+            Vec2::new(0.0, 0.0),
+        ];
+        for (corner, expected) in rect.corners().into_iter().zip(expected) {
+            assert_close(corner, expected);
+        }
+
+        let bounds = rect.bounds();
+        assert_close(bounds.min, Vec2::new(0.0, 0.0));
+        assert_close(bounds.max, Vec2::new(2.0, 4.0));
+
+        // a unit square turned 45 degrees reaches sqrt 2 / 2 along each axis
+        let diamond = OrientedRect::from_aabb(
+            Aabb2::new(Vec2::new(-0.5, -0.5), Vec2::new(0.5, 0.5)),
+            FRAC_PI_4,
+        );
+        assert_close(diamond.corners()[0], Vec2::new(0.0, -SQRT_2 / 2.0));
+        assert_close(diamond.bounds().max, Vec2::new(SQRT_2 / 2.0, SQRT_2 / 2.0));
+    }
+
+    #[test]
+    fn oriented_rect_queries() {
+        let diamond = OrientedRect::new(Vec2::new(0.0, 0.0), Vec2::new(1.0, 1.0), FRAC_PI_4);
+
+        assert_close(
+            diamond.to_local(Vec2::new(SQRT_2, 0.0)),
+            Vec2::new(1.0, -1.0),
+        );
+        assert!(diamond.contains_point(Vec2::new(1.3, 0.0)));
+        // inside the bounds but outside the rect
+        assert!(!diamond.contains_point(Vec2::new(1.0, 1.0)));
+        assert!((diamond.distance(Vec2::new(2.0 * SQRT_2, 0.0)) - SQRT_2).abs() < 1e-5);
+
+        let square = OrientedRect::new(Vec2::new(2.4, 0.0), Vec2::new(1.0, 1.0), 0.0);
+        assert!(diamond.intersects(square));
+        // the bounds overlap but the separating axis of the diamond splits them
+        let corner = OrientedRect::new(Vec2::new(1.9, 1.9), Vec2::new(0.5, 0.5), 0.0);
+        assert!(diamond.bounds().intersects(corner.bounds()));
+        assert!(!diamond.intersects(corner));
+    }
+}
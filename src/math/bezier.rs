@@ -0,0 +1,429 @@
+use super::aabb::Aabb2;
+use super::vector::Vec2;
+use serde::{Deserialize, Serialize};
+
+// deepest subdivision when flattening or measuring, 2^16 pieces is far past any useful tolerance
+const MAX_DEPTH: u32 = 16;
+
+#[repr(C)]
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq)]
+pub struct QuadBezier {
+    pub a: Vec2,
+    pub b: Vec2,
+    pub c: Vec2,
+}
+
+#[repr(C)]
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq)]
+pub struct CubicBezier {
+    pub a: Vec2,
+    pub b: Vec2,
+    pub c: Vec2,
+    pub d: Vec2,
+}
+
+impl QuadBezier {
+    pub const fn new(a: Vec2, b: Vec2, c: Vec2) -> Self {
+        Self { a, b, c }
+    }
+
+    pub fn eval(self, t: f32) -> Vec2 {
+        let mt = 1.0 - t;
+
+        self.a * (mt * mt) + self.b * (2.0 * mt * t) + self.c * (t * t)
+    }
+
+    pub fn derivative(self, t: f32) -> Vec2 {
+        (self.b - self.a) * (2.0 * (1.0 - t)) + (self.c - self.b) * (2.0 * t)
+    }
+
+    /// de Casteljau split into the curves before and after `t`.
+    pub fn split(self, t: f32) -> (Self, Self) {
+        let ab = self.a.lerp(self.b, t);
+        let bc = self.b.lerp(self.c, t);
+        let p = ab.lerp(bc, t);
+
+        (Self::new(self.a, ab, p), Self::new(p, bc, self.c))
+    }
+
+    /// The part of the curve between `t0` and `t1`.
+    pub fn subsegment(self, t0: f32, t1: f32) -> Self {
+        let (_, after) = self.split(t0);
+
+        if t0 >= 1.0 {
+            return after;
+        }
+
+        after.split((t1 - t0) / (1.0 - t0)).0
+    }
+
+    /// The tight bounding box of the curve rather than of its control points.
+    pub fn bounds(self) -> Aabb2 {
+        let mut bounds = Aabb2::from_corners(self.a, self.c);
+
+        // the derivative is linear so each axis has at most one extremum
+        let denominator = self.a - self.b * 2.0 + self.c;
+        let numerator = self.a - self.b;
+
+        for t in [numerator.x / denominator.x, numerator.y / denominator.y] {
+            if t > 0.0 && t < 1.0 {
+                bounds = bounds.expand_to(self.eval(t));
+            }
+        }
+
+        bounds
+    }
+
+    /// Estimated length, accurate to roughly `tolerance`.
+    pub fn arc_length(self, tolerance: f32) -> f32 {
+        arc_length(&[self.a, self.b, self.c], tolerance, 0)
+    }
+
+    /// Calls `f` with line segments that stay within `tolerance` of the curve.
+    pub fn flatten(self, tolerance: f32, mut f: impl FnMut(Vec2, Vec2)) {
+        // a straight line between samples h apart in t deviates by at most |p''| h^2 / 8
+        // and the second derivative of a quadratic is the constant 2 (a - 2b + c)
+        let deviation = (self.a - self.b * 2.0 + self.c).len();
+        let count = (deviation / (4.0 * tolerance.max(f32::EPSILON)))
+            .sqrt()
+            .ceil()
+            .clamp(1.0, f32::from(1u16 << 12));
+
+        let mut start = self.a;
+
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        for i in 1..=count as u32 {
+            #[allow(clippy::cast_precision_loss)]
+            let end = self.eval(i as f32 / count);
+
+            f(start, end);
+            start = end;
+        }
+    }
+
+    /// The same curve as a cubic.
+    pub fn to_cubic(self) -> CubicBezier {
+        CubicBezier::new(
+            self.a,
+            self.a.lerp(self.b, 2.0 / 3.0),
+            self.c.lerp(self.b, 2.0 / 3.0),
+            self.c,
+        )
+    }
+}
+
+impl CubicBezier {
+    pub const fn new(a: Vec2, b: Vec2, c: Vec2, d: Vec2) -> Self {
+        Self { a, b, c, d }
+    }
+
+    pub fn eval(self, t: f32) -> Vec2 {
+        let mt = 1.0 - t;
+
+        self.a * (mt * mt * mt)
+            + self.b * (3.0 * mt * mt * t)
+            + self.c * (3.0 * mt * t * t)
+            + self.d * (t * t * t)
+    }
+
+    pub fn derivative(self, t: f32) -> Vec2 {
+        QuadBezier::new(self.b - self.a, self.c - self.b, self.d - self.c).eval(t) * 3.0
+    }
+
+    /// de Casteljau split into the curves before and after `t`.
+    pub fn split(self, t: f32) -> (Self, Self) {
+        let ab = self.a.lerp(self.b, t);
+        let bc = self.b.lerp(self.c, t);
+        let cd = self.c.lerp(self.d, t);
+        let abc = ab.lerp(bc, t);
+        let bcd = bc.lerp(cd, t);
+        let p = abc.lerp(bcd, t);
+
+        (Self::new(self.a, ab, abc, p), Self::new(p, bcd, cd, self.d))
+    }
+
+    /// The part of the curve between `t0` and `t1`.
+    pub fn subsegment(self, t0: f32, t1: f32) -> Self {
+        let (_, after) = self.split(t0);
+
+        if t0 >= 1.0 {
+            return after;
+        }
+
+        after.split((t1 - t0) / (1.0 - t0)).0
+    }
+
+    /// The tight bounding box of the curve rather than of its control points.
+    pub fn bounds(self) -> Aabb2 {
+        let mut bounds = Aabb2::from_corners(self.a, self.d);
+
+        // the derivative is the quadratic q2 t^2 + q1 t + q0 on each axis
+        let q2 = (self.b - self.c) * 3.0 + self.d - self.a;
+        let q1 = (self.a - self.b * 2.0 + self.c) * 2.0;
+        let q0 = self.b - self.a;
+
+        for (q2, q1, q0) in [(q2.x, q1.x, q0.x), (q2.y, q1.y, q0.y)] {
+            for t in quadratic_roots(q2, q1, q0).into_iter().flatten() {
+                if t > 0.0 && t < 1.0 {
+                    bounds = bounds.expand_to(self.eval(t));
+                }
+            }
+        }
+
+        bounds
+    }
+
+    /// Estimated length, accurate to roughly `tolerance`.
+    pub fn arc_length(self, tolerance: f32) -> f32 {
+        arc_length(&[self.a, self.b, self.c, self.d], tolerance, 0)
+    }
+
+    /// Calls `f` with line segments that stay within `tolerance` of the curve,
+    /// subdividing more where the curve bends more.
+    pub fn flatten(self, tolerance: f32, mut f: impl FnMut(Vec2, Vec2)) {
+        self.flatten_recursive(tolerance.max(f32::EPSILON), 0, &mut f);
+    }
+
+    fn flatten_recursive(self, tolerance: f32, depth: u32, f: &mut impl FnMut(Vec2, Vec2)) {
+        // the control points' distance from the points a third and two thirds along the
+        // chord bounds how far the curve strays from it, 16 tolerance^2 being the flat limit
+        let u = self.b * 3.0 - self.a * 2.0 - self.d;
+        let v = self.c * 3.0 - self.a - self.d * 2.0;
+        let u = u * u;
+        let v = v * v;
+
+        if depth >= MAX_DEPTH || u.x.max(v.x) + u.y.max(v.y) <= 16.0 * tolerance * tolerance {
+            f(self.a, self.d);
+            return;
+        }
+
+        let (first, second) = self.split(0.5);
+
+        first.flatten_recursive(tolerance, depth + 1, f);
+        second.flatten_recursive(tolerance, depth + 1, f);
+    }
+
+    /// Approximates the curve with quadratics that stay within `tolerance` of it,
+    /// the form ttf outlines and the gpu bezier sdf use.
+    pub fn to_quadratics(self, tolerance: f32) -> Vec<QuadBezier> {
+        // the error of the single quadratic approximation is sqrt(3) / 36 |d - 3c + 3b - a|
+        // and shrinks with the cube of the number of pieces
+        let third_difference = (self.d - self.c * 3.0 + self.b * 3.0 - self.a).len();
+        let error = third_difference * 3f32.sqrt() / 36.0;
+
+        let count = (error / tolerance.max(f32::EPSILON))
+            .cbrt()
+            .ceil()
+            .clamp(1.0, f32::from(1u16 << 8));
+
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        let pieces = count as u32;
+
+        (0..pieces)
+            .map(|i| {
+                #[allow(clippy::cast_precision_loss)]
+                let piece = self.subsegment(i as f32 / count, (i + 1) as f32 / count);
+
+                QuadBezier::new(
+                    piece.a,
+                    ((piece.b + piece.c) * 3.0 - piece.a - piece.d) / 4.0,
+                    piece.d,
+                )
+            })
+            .collect()
+    }
+}
+
+impl From<QuadBezier> for CubicBezier {
+    fn from(value: QuadBezier) -> Self {
+        value.to_cubic()
+    }
+}
+
+// real roots of a t^2 + b t + c, falling back to the linear solution when a is zero
+fn quadratic_roots(a: f32, b: f32, c: f32) -> [Option<f32>; 2] {
+    if a.abs() <= f32::EPSILON {
+        return [(b != 0.0).then(|| -c / b), None];
+    }
+
+    let discriminant = b * b - 4.0 * a * c;
+
+    if discriminant < 0.0 {
+        return [None, None];
+    }
+
+    let root = discriminant.sqrt();
+
+    [Some((-b + root) / (2.0 * a)), Some((-b - root) / (2.0 * a))]
+}
+
+// the length lies between the chord and the control polygon, for a curve of degree n
+// (2 chord + (n - 1) polygon) / (n + 1) is a good estimate once the two are close
+fn arc_length(points: &[Vec2], tolerance: f32, depth: u32) -> f32 {
+    let chord = (points[points.len() - 1] - points[0]).len();
+    let polygon: f32 = points.windows(2).map(|w| (w[1] - w[0]).len()).sum();
+
+    if depth >= MAX_DEPTH || polygon - chord <= tolerance {
+        #[allow(clippy::cast_precision_loss)]
+        let degree = (points.len() - 1) as f32;
+
+        return (2.0 * chord + (degree - 1.0) * polygon) / (degree + 1.0);
+    }
+
+    let tolerance = tolerance / 2.0;
+
+    match *points {
+        [a, b, c] => {
+            let (first, second) = QuadBezier::new(a, b, c).split(0.5);
+
+            arc_length(&[first.a, first.b, first.c], tolerance, depth + 1)
+                + arc_length(&[second.a, second.b, second.c], tolerance, depth + 1)
+        }
+        [a, b, c, d] => {
+            let (first, second) = CubicBezier::new(a, b, c, d).split(0.5);
+
+            arc_length(&[first.a, first.b, first.c, first.d], tolerance, depth + 1)
+                + arc_length(
+                    &[second.a, second.b, second.c, second.d],
+                    tolerance,
+                    depth + 1,
+                )
+        }
+        _ => polygon,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: Vec2, expected: Vec2) {
+        assert!(
+            (actual - expected).len() < 1e-5,
+            "{actual:?} != {expected:?}"
+        );
+    }
+
+    fn arch() -> CubicBezier {
+        CubicBezier::new(
+            Vec2::new(0.0, 0.0),
+            Vec2::new(0.0, 1.0),
+            Vec2::new(1.0, 1.0),
+            Vec2::new(1.0, 0.0),
+        )
+    }
+
+    fn s_curve() -> CubicBezier {
+        CubicBezier::new(
+            Vec2::new(0.0, 0.0),
+            Vec2::new(10.0, 0.0),
+            Vec2::new(-5.0, 10.0),
+            Vec2::new(5.0, 10.0),
+        )
+    }
+
+    fn segments(curve: CubicBezier, tolerance: f32) -> Vec<(Vec2, Vec2)> {
+        let mut segments = Vec::new();
+        curve.flatten(tolerance, |a, b| segments.push((a, b)));
+        segments
+    }
+
+    fn distance_to_segment(point: Vec2, (a, b): (Vec2, Vec2)) -> f32 {
+        let t = (point - a).dot(b - a) / (b - a).dot(b - a).max(f32::EPSILON);
+
+        (point - a.lerp(b, t.clamp(0.0, 1.0))).len()
+    }
+
+    #[test]
+    fn bounds() {
+        // the top of the arch is at t = 0.5, between the control points and the ends
+        let bounds = arch().bounds();
+        assert_close(bounds.min, Vec2::new(0.0, 0.0));
+        assert_close(bounds.max, Vec2::new(1.0, 0.75));
+
+        let quad = QuadBezier::new(
+            Vec2::new(0.0, 0.0),
+            Vec2::new(1.0, 2.0),
+            Vec2::new(2.0, 0.0),
+        );
+        let bounds = quad.bounds();
+        assert_close(bounds.min, Vec2::new(0.0, 0.0));
+        assert_close(bounds.max, Vec2::new(2.0, 1.0));
+        assert_eq!(quad.to_cubic().bounds(), bounds);
+
+        // every point of the curve is inside
+        let curve = s_curve();
+        let bounds = curve.bounds();
+        for i in 0..=100 {
+            #[allow(clippy::cast_precision_loss)]
+            let point = curve.eval(i as f32 / 100.0);
+            assert!(
+                bounds.expand_to(point) == bounds,
+                "{point:?} outside {bounds:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn split_is_continuous() {
+        let curve = s_curve();
+        let (first, second) = curve.split(0.5);
+
+        assert_eq!(first.a, curve.a);
+        assert_eq!(second.d, curve.d);
+        assert_eq!(first.d, second.a);
+        assert_close(first.d, curve.eval(0.5));
+        // both halves are traversed at half the speed, so the tangents match at the join
+        assert_close(first.derivative(1.0), second.derivative(0.0));
+        assert_close(first.derivative(1.0) * 2.0, curve.derivative(0.5));
+
+        for t in [0.1, 0.4, 0.9] {
+            assert_close(first.eval(t), curve.eval(t / 2.0));
+            assert_close(second.eval(t), curve.eval(0.5 + t / 2.0));
+        }
+    }
+
+    #[test]
+    fn flatten_stays_within_tolerance() {
+        for tolerance in [1.0, 0.1, 0.01] {
+            let curve = s_curve();
+            let segments = segments(curve, tolerance);
+
+            assert_eq!(segments[0].0, curve.a);
+            assert_eq!(segments[segments.len() - 1].1, curve.d);
+            assert!(segments.windows(2).all(|w| w[0].1 == w[1].0));
+
+            for i in 0..=1000 {
+                #[allow(clippy::cast_precision_loss)]
+                let point = curve.eval(i as f32 / 1000.0);
+                let distance = segments
+                    .iter()
+                    .map(|&segment| distance_to_segment(point, segment))
+                    .fold(f32::INFINITY, f32::min);
+
+                assert!(distance <= tolerance, "{distance} > {tolerance}");
+            }
+        }
+
+        // a tighter tolerance needs more segments
+        assert!(segments(s_curve(), 0.01).len() > segments(s_curve(), 1.0).len());
+    }
+
+    #[test]
+    fn flatten_clamps_the_tolerance() {
+        // a negative tolerance is clamped like a zero one rather than being squared
+        // into a positive flat limit
+        let straight = CubicBezier::new(
+            Vec2::new(0.0, 0.0),
+            Vec2::new(1.0, 0.0),
+            Vec2::new(2.0, 0.0),
+            Vec2::new(3.0, 0.0),
+        );
+        assert_eq!(segments(straight, 0.0).len(), 1);
+        assert_eq!(segments(straight, -1.0).len(), 1);
+
+        let curve = segments(arch(), -1.0);
+        assert_eq!(curve.len(), segments(arch(), 0.0).len());
+        assert_eq!(curve[curve.len() - 1].1, arch().d);
+    }
+}
@@ -1,3 +1,6 @@
+pub mod aabb;
+pub mod bezier;
+
 pub trait Zero {
     const ZERO: Self;
 }
@@ -14,6 +17,16 @@ pub trait Atan2 {
     fn atan2(self, other: Self) -> Self;
 }
 
+pub trait Floor {
+    fn floor(self) -> Self;
+}
+
+pub trait Trig {
+    fn sin(self) -> Self;
+    fn cos(self) -> Self;
+    fn acos(self) -> Self;
+}
+
 macro_rules! number {
     ($type: ty, $zero: expr, $identity: expr) => {
         impl Zero for $type {
@@ -47,6 +60,27 @@ macro_rules! sqrt {
     };
 }
 
+macro_rules! floor {
+    (float, $($type: ty)+) => {
+        $(
+            impl Floor for $type {
+                fn floor(self) -> Self {
+                    self.floor()
+                }
+            }
+        )+
+    };
+    (int, $($type: ty)+) => {
+        $(
+            impl Floor for $type {
+                fn floor(self) -> Self {
+                    self
+                }
+            }
+        )+
+    };
+}
+
 macro_rules! atan2 {
     ($($type: ty)+) => {
         $(
@@ -59,9 +93,32 @@ macro_rules! atan2 {
     };
 }
 
+macro_rules! trig {
+    ($($type: ty)+) => {
+        $(
+            impl Trig for $type {
+                fn sin(self) -> Self {
+                    self.sin()
+                }
+
+                fn cos(self) -> Self {
+                    self.cos()
+                }
+
+                fn acos(self) -> Self {
+                    self.acos()
+                }
+            }
+        )+
+    };
+}
+
 sqrt!(float, f32 f64);
 sqrt!(int, i32 i64 u32 u64);
 
+floor!(float, f32 f64);
+floor!(int, i32 i64 u32 u64);
+
 number!(i32, 0, 1);
 number!(i64, 0, 1);
 number!(u32, 0, 1);
@@ -70,13 +127,73 @@ number!(f32, 0.0, 1.0);
 number!(f64, 0.0, 1.0);
 
 atan2!(f32 f64);
+trig!(f32 f64);
 
 pub mod vector {
-    use super::{Identity, Sqrt, Zero};
+    use super::{Floor, Identity, Sqrt, Zero};
     use crate::util::{as_u8_slice, as_u8_slice_from_slice, AsBytes};
     use serde::{Deserialize, Serialize};
     use std::ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Sub, SubAssign};
 
+    // substitutes a token, used to repeat something once per field
+    macro_rules! replace {
+        ($_from: tt $to: tt) => {
+            $to
+        };
+    }
+
+    // generates every combination of the given fields as methods, `v.zyx()`, `v.xxyy()`
+    macro_rules! swizzle {
+        (@2 [$($a: ident)+] $all: tt) => {
+            $(swizzle!(@2 $a $all);)+
+        };
+        (@2 $a: ident [$($b: ident)+]) => {
+            paste::paste! {
+                $(
+                    #[inline(always)]
+                    pub fn [<$a $b>](self) -> Vec2<T> {
+                        Vec2::new(self.$a, self.$b)
+                    }
+                )+
+            }
+        };
+        (@3 [$($a: ident)+] $all: tt) => {
+            $(swizzle!(@3 $a $all $all);)+
+        };
+        (@3 $a: ident [$($b: ident)+] $all: tt) => {
+            $(swizzle!(@3 $a $b $all);)+
+        };
+        (@3 $a: ident $b: ident [$($c: ident)+]) => {
+            paste::paste! {
+                $(
+                    #[inline(always)]
+                    pub fn [<$a $b $c>](self) -> Vec3<T> {
+                        Vec3::new(self.$a, self.$b, self.$c)
+                    }
+                )+
+            }
+        };
+        (@4 [$($a: ident)+] $all: tt) => {
+            $(swizzle!(@4 $a $all $all $all);)+
+        };
+        (@4 $a: ident [$($b: ident)+] $all: tt $all2: tt) => {
+            $(swizzle!(@4 $a $b $all $all2);)+
+        };
+        (@4 $a: ident $b: ident [$($c: ident)+] $all: tt) => {
+            $(swizzle!(@4 $a $b $c $all);)+
+        };
+        (@4 $a: ident $b: ident $c: ident [$($d: ident)+]) => {
+            paste::paste! {
+                $(
+                    #[inline(always)]
+                    pub fn [<$a $b $c $d>](self) -> Vec4<T> {
+                        Vec4::new(self.$a, self.$b, self.$c, self.$d)
+                    }
+                )+
+            }
+        };
+    }
+
     macro_rules! create_vec {
         ($name: ident, $($fields: ident)+) => {
             #[repr(C)]
@@ -115,40 +232,185 @@ pub mod vector {
                 }
             }
 
-            impl<T: Add<Output = T> + Div<Output = T> + Zero + Mul<Output = T> + Copy> $name<T> {
+            impl<T: Add<Output = T> + Div<Output = T> + Zero + Mul<Output = T> + PartialEq + Copy> $name<T> {
+                /// Returns zero when projecting onto the zero vector.
                 #[inline(always)]
                 pub fn proj(self, to: Self) -> Self {
-                    to * (self.dot(to) / to.dot(to))
+                    let len_squared = to.dot(to);
+
+                    if len_squared == T::ZERO {
+                        Self::ZERO
+                    } else {
+                        to * (self.dot(to) / len_squared)
+                    }
                 }
             }
 
-            impl<T: Add<Output = T> + Div<Output = T> + Mul<Output = T> + Zero + Sqrt + Eq + Copy> $name<T> {
+            impl<T: Add<Output = T> + Div<Output = T> + Mul<Output = T> + Zero + Sqrt + PartialEq + Copy> $name<T> {
+                /// Returns zero for the zero vector. Integer vectors are divided by their
+                /// truncated length, so only axis aligned ones come out with a length of one,
+                /// `Vec2::new(3, 4).normalized()` is zero.
                 #[inline(always)]
                 pub fn normalized(self) -> Self {
                     let len = self.len();
 
                     if len == T::ZERO {
-                        self / len
-                    } else {
                         Self::ZERO
+                    } else {
+                        self / len
                     }
-
                 }
             }
 
             impl<T: Add<Output = T> + Div<Output = T> + Mul<Output = T> + Zero + Sqrt + PartialOrd + Copy> $name<T> {
+                /// Integer vectors are scaled with truncating division, so the result can be
+                /// shorter than `limit`.
                 #[inline(always)]
                 pub fn limit_length(self, limit: T) -> Self {
                     let len = self.len();
 
                     if len > limit {
-                        self / self.len()
+                        self * limit / len
                     } else {
                         self
                     }
                 }
             }
 
+            impl<T: PartialOrd + Copy> $name<T> {
+                #[inline(always)]
+                pub fn min(self, other: Self) -> Self {
+                    Self {
+                        $($fields: if other.$fields < self.$fields { other.$fields } else { self.$fields },)+
+                    }
+                }
+
+                #[inline(always)]
+                pub fn max(self, other: Self) -> Self {
+                    Self {
+                        $($fields: if other.$fields > self.$fields { other.$fields } else { self.$fields },)+
+                    }
+                }
+
+                #[inline(always)]
+                pub fn clamp(self, min: Self, max: Self) -> Self {
+                    self.max(min).min(max)
+                }
+            }
+
+            impl<T: PartialOrd + Neg<Output = T> + Zero + Copy> $name<T> {
+                #[inline(always)]
+                pub fn abs(self) -> Self {
+                    Self {
+                        $($fields: if self.$fields < T::ZERO { -self.$fields } else { self.$fields },)+
+                    }
+                }
+            }
+
+            impl<T: Floor + Sub<Output = T> + Copy> $name<T> {
+                #[inline(always)]
+                pub fn floor(self) -> Self {
+                    Self {
+                        $($fields: self.$fields.floor(),)+
+                    }
+                }
+
+                /// `self - floor(self)` like wgsl, so the result is always positive.
+                #[inline(always)]
+                pub fn fract(self) -> Self {
+                    self - self.floor()
+                }
+            }
+
+            impl<T: Add<Output = T> + Sub<Output = T> + Mul<Output = T> + Identity + Copy> $name<T> {
+                /// Same as wgsl's `mix`.
+                #[inline(always)]
+                pub fn lerp(self, other: Self, t: T) -> Self {
+                    self * (T::IDENTITY - t) + other * t
+                }
+            }
+
+            impl<T: PartialOrd + Zero + Identity + Copy> $name<T> {
+                /// Same as wgsl's `step(edge, self)`, one where `self >= edge` and zero elsewhere.
+                #[inline(always)]
+                pub fn step(self, edge: Self) -> Self {
+                    Self {
+                        $($fields: if self.$fields >= edge.$fields { T::IDENTITY } else { T::ZERO },)+
+                    }
+                }
+            }
+
+            impl<
+                T: Add<Output = T>
+                    + Sub<Output = T>
+                    + Mul<Output = T>
+                    + Div<Output = T>
+                    + PartialOrd
+                    + Zero
+                    + Identity
+                    + Copy
+            > $name<T> {
+                /// Same as wgsl's `smoothstep(low, high, self)`. Where `low` and `high` are equal
+                /// the edge is a step, one where `self >= low` and zero elsewhere.
+                #[inline(always)]
+                pub fn smoothstep(self, low: Self, high: Self) -> Self {
+                    let two = T::IDENTITY + T::IDENTITY;
+                    let three = two + T::IDENTITY;
+                    let t = Self {
+                        $($fields: if high.$fields == low.$fields {
+                            if self.$fields >= low.$fields { T::IDENTITY } else { T::ZERO }
+                        } else {
+                            (self.$fields - low.$fields) / (high.$fields - low.$fields)
+                        },)+
+                    }
+                    .clamp(Self::ZERO, Self::IDENTITY);
+
+                    t * t * (Self::IDENTITY * three - t * two)
+                }
+            }
+
+            impl<T: Copy> $name<T> {
+                swizzle!(@2 [$($fields)+] [$($fields)+]);
+                swizzle!(@3 [$($fields)+] [$($fields)+]);
+                swizzle!(@4 [$($fields)+] [$($fields)+]);
+            }
+
+            impl<T> From<[T; 0 $(+ replace!($fields 1))+]> for $name<T> {
+                #[inline(always)]
+                fn from(value: [T; 0 $(+ replace!($fields 1))+]) -> Self {
+                    let [$($fields,)+] = value;
+
+                    Self {
+                        $($fields,)+
+                    }
+                }
+            }
+
+            impl<T> From<$name<T>> for [T; 0 $(+ replace!($fields 1))+] {
+                #[inline(always)]
+                fn from(value: $name<T>) -> Self {
+                    [$(value.$fields,)+]
+                }
+            }
+
+            impl<T> From<($(replace!($fields T),)+)> for $name<T> {
+                #[inline(always)]
+                fn from(value: ($(replace!($fields T),)+)) -> Self {
+                    let ($($fields,)+) = value;
+
+                    Self {
+                        $($fields,)+
+                    }
+                }
+            }
+
+            impl<T> From<$name<T>> for ($(replace!($fields T),)+) {
+                #[inline(always)]
+                fn from(value: $name<T>) -> Self {
+                    ($(value.$fields,)+)
+                }
+            }
+
             impl<'a, T> AsBytes<'a> for $name<T> {
                 #[inline(always)]
                 fn as_bytes(&'a self) -> &'a [u8] {
@@ -309,38 +571,168 @@ pub mod vector {
         }
     }
 
+    impl<T> Vec2<T> {
+        #[inline]
+        pub fn extend(self, z: T) -> Vec3<T> {
+            Vec3::new(self.x, self.y, z)
+        }
+    }
+
+    impl<T> Vec3<T> {
+        #[inline]
+        pub fn extend(self, w: T) -> Vec4<T> {
+            Vec4::new(self.x, self.y, self.z, w)
+        }
+
+        #[inline]
+        pub fn truncate(self) -> Vec2<T> {
+            Vec2::new(self.x, self.y)
+        }
+    }
+
+    impl<T> Vec4<T> {
+        #[inline]
+        pub fn truncate(self) -> Vec3<T> {
+            Vec3::new(self.x, self.y, self.z)
+        }
+    }
+
     create_vec!(Vec2, x y);
     create_vec!(Vec3, x y z);
     create_vec!(Vec4, x y z w);
 }
 
-mod matrix {
+pub mod matrix {
     use super::vector::{Vec2, Vec3};
-    use super::{vector::Vec4, Identity, Zero};
-    use std::ops::{Add, Mul};
+    use super::{vector::Vec4, Identity, Sqrt, Trig, Zero};
+    use crate::util::{as_u8_slice, as_u8_slice_from_slice, AsBytes};
+    use serde::{Deserialize, Serialize};
+    use std::ops::{Add, AddAssign, Div, Mul, MulAssign, Neg, Sub, SubAssign};
 
+    // Matrices are stored column major, each field is a column. This is the
+    // same memory layout wgsl uses so a matrix can be written to a buffer as is.
+    // Note that wgsl pads the columns of a mat3x3 to 16 bytes, Mat3 is not padded.
     macro_rules! create_matrix {
         ($name: ident, $vec: ident, $($field: ident)+) => {
-            struct $name<T> {
+            #[repr(C)]
+            #[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq)]
+            pub struct $name<T = f32> {
                 $(pub $field: $vec<T>,)+
             }
 
+            impl<T> $name<T> {
+                #[inline(always)]
+                pub const fn new($($field: $vec<T>,)+) -> Self {
+                    Self {
+                        $($field,)+
+                    }
+                }
+            }
+
+            impl<'a, T> AsBytes<'a> for $name<T> {
+                #[inline(always)]
+                fn as_bytes(&'a self) -> &'a [u8] {
+                    unsafe { as_u8_slice(self) }
+                }
+            }
+
+            impl<'a, T> AsBytes<'a> for [$name<T>] {
+                #[inline(always)]
+                fn as_bytes(&'a self) -> &'a [u8] {
+                    unsafe { as_u8_slice_from_slice(self) }
+                }
+            }
+
             impl<T: Zero> Zero for $name<T> {
                 const ZERO: Self = Self {
                     $($field: $vec::ZERO,)+
                 };
             }
 
+            impl<T: Add<Output = T>> Add for $name<T> {
+                type Output = Self;
+
+                #[inline(always)]
+                fn add(self, rhs: Self) -> Self::Output {
+                    Self {
+                        $($field: self.$field + rhs.$field,)+
+                    }
+                }
+            }
+
+            impl<T: Add<Output = T> + Copy> AddAssign for $name<T> {
+                #[inline(always)]
+                fn add_assign(&mut self, rhs: Self) {
+                    $(self.$field = self.$field + rhs.$field;)+
+                }
+            }
+
+            impl<T: Sub<Output = T>> Sub for $name<T> {
+                type Output = Self;
+
+                #[inline(always)]
+                fn sub(self, rhs: Self) -> Self::Output {
+                    Self {
+                        $($field: self.$field - rhs.$field,)+
+                    }
+                }
+            }
+
+            impl<T: Sub<Output = T> + Copy> SubAssign for $name<T> {
+                #[inline(always)]
+                fn sub_assign(&mut self, rhs: Self) {
+                    $(self.$field = self.$field - rhs.$field;)+
+                }
+            }
+
+            impl<T: Mul<Output = T> + Copy> Mul<T> for $name<T> {
+                type Output = Self;
+
+                #[inline(always)]
+                fn mul(self, rhs: T) -> Self::Output {
+                    Self {
+                        $($field: self.$field * rhs,)+
+                    }
+                }
+            }
+
+            impl<T: Div<Output = T> + Copy> Div<T> for $name<T> {
+                type Output = Self;
+
+                #[inline(always)]
+                fn div(self, rhs: T) -> Self::Output {
+                    Self {
+                        $($field: self.$field / rhs,)+
+                    }
+                }
+            }
+
             impl<T: Add<Output = T> + Mul<Output = T> + Copy + Zero> Mul<$vec<T>> for $name<T> {
                 type Output = $vec<T>;
 
                 #[inline(always)]
                 fn mul(self, rhs: $vec<T>) -> Self::Output {
-                    Self::Output {
-                        $($field: rhs.dot(self.$field),)+
+                    $(self.$field * rhs.$field + )+ $vec::ZERO
+                }
+            }
+
+            impl<T: Add<Output = T> + Mul<Output = T> + Copy + Zero> Mul for $name<T> {
+                type Output = Self;
+
+                #[inline(always)]
+                fn mul(self, rhs: Self) -> Self::Output {
+                    Self {
+                        $($field: self * rhs.$field,)+
                     }
                 }
             }
+
+            impl<T: Add<Output = T> + Mul<Output = T> + Copy + Zero> MulAssign for $name<T> {
+                #[inline(always)]
+                fn mul_assign(&mut self, rhs: Self) {
+                    *self = *self * rhs;
+                }
+            }
         };
     }
 
@@ -371,28 +763,773 @@ mod matrix {
             w: Vec4::new(T::ZERO, T::ZERO, T::ZERO, T::IDENTITY),
         };
     }
-}
 
-mod quaternion {
-    use super::{vector::Vec3, Atan2, Identity, Sqrt, Zero};
-    use std::ops::{Add, Div, Mul};
+    impl<T: Copy> Mat2<T> {
+        #[inline]
+        pub fn transpose(self) -> Self {
+            Self {
+                x: Vec2::new(self.x.x, self.y.x),
+                y: Vec2::new(self.x.y, self.y.y),
+            }
+        }
+    }
+
+    impl<T: Copy> Mat3<T> {
+        #[inline]
+        pub fn transpose(self) -> Self {
+            Self {
+                x: Vec3::new(self.x.x, self.y.x, self.z.x),
+                y: Vec3::new(self.x.y, self.y.y, self.z.y),
+                z: Vec3::new(self.x.z, self.y.z, self.z.z),
+            }
+        }
+    }
+
+    impl<T: Copy> Mat4<T> {
+        #[inline]
+        pub fn transpose(self) -> Self {
+            Self {
+                x: Vec4::new(self.x.x, self.y.x, self.z.x, self.w.x),
+                y: Vec4::new(self.x.y, self.y.y, self.z.y, self.w.y),
+                z: Vec4::new(self.x.z, self.y.z, self.z.z, self.w.z),
+                w: Vec4::new(self.x.w, self.y.w, self.z.w, self.w.w),
+            }
+        }
+    }
+
+    impl<T: Mul<Output = T> + Sub<Output = T> + Div<Output = T> + Zero + PartialEq + Copy> Mat2<T> {
+        #[inline]
+        pub fn determinant(self) -> T {
+            self.x.x * self.y.y - self.y.x * self.x.y
+        }
+
+        /// Returns `None` if the matrix is singular.
+        pub fn inverse(self) -> Option<Self> {
+            let det = self.determinant();
+
+            if det == T::ZERO {
+                return None;
+            }
 
-    pub struct Quat<T>(Vec3<T>, T);
+            Some(Self {
+                x: Vec2::new(self.y.y / det, (T::ZERO - self.x.y) / det),
+                y: Vec2::new((T::ZERO - self.y.x) / det, self.x.x / det),
+            })
+        }
+    }
 
     impl<
             T: Add<Output = T>
                 + Mul<Output = T>
+                + Sub<Output = T>
                 + Div<Output = T>
-                + Identity
                 + Zero
-                + Sqrt
+                + PartialEq
+                + Copy,
+        > Mat3<T>
+    {
+        #[inline]
+        pub fn determinant(self) -> T {
+            self.x.dot(self.y.cross(self.z))
+        }
+
+        /// Returns `None` if the matrix is singular.
+        pub fn inverse(self) -> Option<Self> {
+            let det = self.determinant();
+
+            if det == T::ZERO {
+                return None;
+            }
+
+            // the rows of the inverse are the cross products of the columns
+            let adjugate = Self {
+                x: self.y.cross(self.z),
+                y: self.z.cross(self.x),
+                z: self.x.cross(self.y),
+            };
+
+            Some(adjugate.transpose() / det)
+        }
+    }
+
+    impl<
+            T: Add<Output = T>
+                + Mul<Output = T>
+                + Sub<Output = T>
+                + Div<Output = T>
+                + Zero
+                + PartialEq
+                + Copy,
+        > Mat4<T>
+    {
+        // 2x2 sub determinants of the upper (columns x, y) and lower (columns z, w) halves
+        #[inline]
+        fn sub_determinants(self) -> ([T; 6], [T; 6]) {
+            let (a, b, c, d) = (self.x, self.y, self.z, self.w);
+
+            (
+                [
+                    a.x * b.y - a.y * b.x,
+                    a.x * b.z - a.z * b.x,
+                    a.x * b.w - a.w * b.x,
+                    a.y * b.z - a.z * b.y,
+                    a.y * b.w - a.w * b.y,
+                    a.z * b.w - a.w * b.z,
+                ],
+                [
+                    c.x * d.y - c.y * d.x,
+                    c.x * d.z - c.z * d.x,
+                    c.x * d.w - c.w * d.x,
+                    c.y * d.z - c.z * d.y,
+                    c.y * d.w - c.w * d.y,
+                    c.z * d.w - c.w * d.z,
+                ],
+            )
+        }
+
+        #[inline]
+        pub fn determinant(self) -> T {
+            let ([s0, s1, s2, s3, s4, s5], [c0, c1, c2, c3, c4, c5]) = self.sub_determinants();
+
+            s0 * c5 - s1 * c4 + s2 * c3 + s3 * c2 - s4 * c1 + s5 * c0
+        }
+
+        /// Returns `None` if the matrix is singular.
+        pub fn inverse(self) -> Option<Self> {
+            let ([s0, s1, s2, s3, s4, s5], [c0, c1, c2, c3, c4, c5]) = self.sub_determinants();
+
+            let det = s0 * c5 - s1 * c4 + s2 * c3 + s3 * c2 - s4 * c1 + s5 * c0;
+
+            if det == T::ZERO {
+                return None;
+            }
+
+            let (a, b, c, d) = (self.x, self.y, self.z, self.w);
+
+            let adjugate = Self {
+                x: Vec4::new(
+                    b.y * c5 - b.z * c4 + b.w * c3,
+                    a.z * c4 - a.y * c5 - a.w * c3,
+                    d.y * s5 - d.z * s4 + d.w * s3,
+                    c.z * s4 - c.y * s5 - c.w * s3,
+                ),
+                y: Vec4::new(
+                    b.z * c2 - b.x * c5 - b.w * c1,
+                    a.x * c5 - a.z * c2 + a.w * c1,
+                    d.z * s2 - d.x * s5 - d.w * s1,
+                    c.x * s5 - c.z * s2 + c.w * s1,
+                ),
+                z: Vec4::new(
+                    b.x * c4 - b.y * c2 + b.w * c0,
+                    a.y * c2 - a.x * c4 - a.w * c0,
+                    d.x * s4 - d.y * s2 + d.w * s0,
+                    c.y * s2 - c.x * s4 - c.w * s0,
+                ),
+                w: Vec4::new(
+                    b.y * c1 - b.x * c3 - b.z * c0,
+                    a.x * c3 - a.y * c1 + a.z * c0,
+                    d.y * s1 - d.x * s3 - d.z * s0,
+                    c.x * s3 - c.y * s1 + c.z * s0,
+                ),
+            };
+
+            Some(adjugate / det)
+        }
+    }
+
+    impl<T: Zero + Copy> Mat2<T> {
+        #[inline]
+        pub fn from_scale(scale: Vec2<T>) -> Self {
+            Self::new(Vec2::new(scale.x, T::ZERO), Vec2::new(T::ZERO, scale.y))
+        }
+    }
+
+    impl<
+            T: Add<Output = T> + Sub<Output = T> + Mul<Output = T> + Zero + Identity + Trig + Copy,
+        > Mat2<T>
+    {
+        /// Counter clockwise rotation, `angle` is in radians.
+        #[inline]
+        pub fn from_angle(angle: T) -> Self {
+            let (sin, cos) = (angle.sin(), angle.cos());
+
+            Self::new(Vec2::new(cos, sin), Vec2::new(T::ZERO - sin, cos))
+        }
+    }
+
+    impl<T: Zero + Copy> Mat3<T> {
+        #[inline]
+        pub fn from_scale(scale: Vec3<T>) -> Self {
+            Self::new(
+                Vec3::new(scale.x, T::ZERO, T::ZERO),
+                Vec3::new(T::ZERO, scale.y, T::ZERO),
+                Vec3::new(T::ZERO, T::ZERO, scale.z),
+            )
+        }
+    }
+
+    impl<
+            T: Add<Output = T> + Sub<Output = T> + Mul<Output = T> + Zero + Identity + Trig + Copy,
+        > Mat3<T>
+    {
+        /// `axis` is expected to be normalized and `angle` is in radians.
+        pub fn from_axis_angle(axis: Vec3<T>, angle: T) -> Self {
+            let (sin, cos) = (angle.sin(), angle.cos());
+            let Vec3 { x, y, z } = axis;
+            let t = T::IDENTITY - cos;
+
+            Self::new(
+                Vec3::new(t * x * x + cos, t * x * y + sin * z, t * x * z - sin * y),
+                Vec3::new(t * x * y - sin * z, t * y * y + cos, t * y * z + sin * x),
+                Vec3::new(t * x * z + sin * y, t * y * z - sin * x, t * z * z + cos),
+            )
+        }
+    }
+
+    impl<T: Zero + Identity + Copy> Mat4<T> {
+        #[inline]
+        pub fn from_translation(translation: Vec3<T>) -> Self {
+            Self::new(
+                Vec4::new(T::IDENTITY, T::ZERO, T::ZERO, T::ZERO),
+                Vec4::new(T::ZERO, T::IDENTITY, T::ZERO, T::ZERO),
+                Vec4::new(T::ZERO, T::ZERO, T::IDENTITY, T::ZERO),
+                Vec4::new(translation.x, translation.y, translation.z, T::IDENTITY),
+            )
+        }
+
+        #[inline]
+        pub fn from_scale(scale: Vec3<T>) -> Self {
+            Self::new(
+                Vec4::new(scale.x, T::ZERO, T::ZERO, T::ZERO),
+                Vec4::new(T::ZERO, scale.y, T::ZERO, T::ZERO),
+                Vec4::new(T::ZERO, T::ZERO, scale.z, T::ZERO),
+                Vec4::new(T::ZERO, T::ZERO, T::ZERO, T::IDENTITY),
+            )
+        }
+    }
+
+    impl<T: Zero + Identity + Copy> From<Mat3<T>> for Mat4<T> {
+        fn from(value: Mat3<T>) -> Self {
+            let Mat3 { x, y, z } = value;
+
+            Self::new(
+                Vec4::new(x.x, x.y, x.z, T::ZERO),
+                Vec4::new(y.x, y.y, y.z, T::ZERO),
+                Vec4::new(z.x, z.y, z.z, T::ZERO),
+                Vec4::new(T::ZERO, T::ZERO, T::ZERO, T::IDENTITY),
+            )
+        }
+    }
+
+    impl<
+            T: Add<Output = T>
+                + Sub<Output = T>
+                + Mul<Output = T>
+                + Div<Output = T>
+                + Neg<Output = T>
+                + Zero
+                + Identity
+                + Sqrt
+                + Trig
+                + Copy,
+        > Mat4<T>
+    {
+        /// `axis` is expected to be normalized and `angle` is in radians.
+        #[inline]
+        pub fn from_axis_angle(axis: Vec3<T>, angle: T) -> Self {
+            Mat3::from_axis_angle(axis, angle).into()
+        }
+
+        /// Right handed view matrix looking from `eye` towards `target`.
+        pub fn look_at(eye: Vec3<T>, target: Vec3<T>, up: Vec3<T>) -> Self {
+            let forward = target - eye;
+            let forward = forward / forward.len();
+            let side = forward.cross(up);
+            let side = side / side.len();
+            let up = side.cross(forward);
+
+            Self::new(
+                Vec4::new(side.x, up.x, -forward.x, T::ZERO),
+                Vec4::new(side.y, up.y, -forward.y, T::ZERO),
+                Vec4::new(side.z, up.z, -forward.z, T::ZERO),
+                Vec4::new(-side.dot(eye), -up.dot(eye), forward.dot(eye), T::IDENTITY),
+            )
+        }
+
+        /// Right handed perspective projection mapping depth to wgpu's 0..1 range.
+        /// `fov_y` is in radians.
+        pub fn perspective(fov_y: T, aspect: T, near: T, far: T) -> Self {
+            let half = fov_y / (T::IDENTITY + T::IDENTITY);
+            let focal = half.cos() / half.sin();
+            let range = T::IDENTITY / (near - far);
+
+            Self::new(
+                Vec4::new(focal / aspect, T::ZERO, T::ZERO, T::ZERO),
+                Vec4::new(T::ZERO, focal, T::ZERO, T::ZERO),
+                Vec4::new(T::ZERO, T::ZERO, far * range, -T::IDENTITY),
+                Vec4::new(T::ZERO, T::ZERO, near * far * range, T::ZERO),
+            )
+        }
+
+        /// Right handed orthographic projection mapping depth to wgpu's 0..1 range.
+        pub fn orthographic(left: T, right: T, bottom: T, top: T, near: T, far: T) -> Self {
+            let two = T::IDENTITY + T::IDENTITY;
+            let width = T::IDENTITY / (right - left);
+            let height = T::IDENTITY / (top - bottom);
+            let range = T::IDENTITY / (near - far);
+
+            Self::new(
+                Vec4::new(two * width, T::ZERO, T::ZERO, T::ZERO),
+                Vec4::new(T::ZERO, two * height, T::ZERO, T::ZERO),
+                Vec4::new(T::ZERO, T::ZERO, range, T::ZERO),
+                Vec4::new(
+                    -(left + right) * width,
+                    -(top + bottom) * height,
+                    near * range,
+                    T::IDENTITY,
+                ),
+            )
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use std::f32::consts::FRAC_PI_2;
+
+        fn assert_close(actual: Vec4, expected: Vec4) {
+            assert!(
+                (actual - expected).len() < 1e-5,
+                "{actual:?} is not {expected:?}"
+            );
+        }
+
+        /// Where `point` ends up after the projection divides by w.
+        fn project(matrix: Mat4, point: Vec3) -> Vec3 {
+            let clip = matrix * point.extend(1.0);
+
+            clip.truncate() / clip.w
+        }
+
+        fn assert_close_mat(actual: Mat4, expected: Mat4) {
+            for (a, b) in [
+                (actual.x, expected.x),
+                (actual.y, expected.y),
+                (actual.z, expected.z),
+                (actual.w, expected.w),
+            ] {
+                assert_close(a, b);
+            }
+        }
+
+        /// Columns of a matrix without any structure to it.
+        fn arbitrary() -> Mat4 {
+            Mat4::new(
+                Vec4::new(2.0, 0.0, 1.0, 3.0),
+                Vec4::new(1.0, 3.0, 0.0, 1.0),
+                Vec4::new(0.0, 1.0, 4.0, 2.0),
+                Vec4::new(1.0, 2.0, 0.0, 5.0),
+            )
+        }
+
+        // the products are whole numbers so they are compared exactly
+        #[test]
+        #[allow(clippy::float_cmp)]
+        fn products() {
+            // rows 1 2, 3 4 times rows 5 6, 7 8
+            let a = Mat2::new(Vec2::new(1.0, 3.0), Vec2::new(2.0, 4.0));
+            let b = Mat2::new(Vec2::new(5.0, 7.0), Vec2::new(6.0, 8.0));
+
+            assert_eq!(
+                a * b,
+                Mat2::new(Vec2::new(19.0, 43.0), Vec2::new(22.0, 50.0))
+            );
+            assert_eq!(a * Vec2::new(1.0, 1.0), Vec2::new(3.0, 7.0));
+            assert_eq!(a * Mat2::IDENTITY, a);
+
+            let mut m = arbitrary();
+            m *= Mat4::IDENTITY;
+            assert_eq!(m, arbitrary());
+            assert_eq!(Mat4::<f32>::IDENTITY * m, m);
+
+            // the product applies the right matrix first
+            let moved = Mat4::from_translation(Vec3::new(1.0, 0.0, 0.0))
+                * Mat4::from_scale(Vec3::new(2.0, 2.0, 2.0));
+            assert_eq!(
+                moved * Vec4::new(1.0, 1.0, 1.0, 1.0),
+                Vec4::new(3.0, 2.0, 2.0, 1.0)
+            );
+        }
+
+        #[test]
+        #[allow(clippy::float_cmp)]
+        fn determinant() {
+            // rows 6 1 1, 4 -2 5, 2 8 7
+            let m = Mat3::new(
+                Vec3::new(6.0, 4.0, 2.0),
+                Vec3::new(1.0, -2.0, 8.0),
+                Vec3::new(1.0, 5.0, 7.0),
+            );
+
+            assert_eq!(m.determinant(), -306.0);
+            assert_eq!(m.transpose().determinant(), -306.0);
+            assert_eq!(
+                Mat2::new(Vec2::new(1.0, 3.0), Vec2::new(2.0, 4.0)).determinant(),
+                -2.0
+            );
+            assert_eq!(
+                Mat4::from_scale(Vec3::new(2.0, 3.0, 4.0)).determinant(),
+                24.0
+            );
+            assert_eq!(
+                arbitrary().determinant(),
+                arbitrary().transpose().determinant()
+            );
+            assert_eq!(Mat4::from(m).determinant(), -306.0);
+        }
+
+        #[test]
+        fn inverse() {
+            let m = arbitrary();
+            let inverse = m.inverse().expect("the matrix is invertible");
+
+            assert_close_mat(m * inverse, Mat4::IDENTITY);
+            assert_close_mat(inverse * m, Mat4::IDENTITY);
+
+            let m3 = Mat3::new(
+                Vec3::new(6.0, 4.0, 2.0),
+                Vec3::new(1.0, -2.0, 8.0),
+                Vec3::new(1.0, 5.0, 7.0),
+            );
+            let inverse = m3.inverse().expect("the matrix is invertible");
+            assert_close_mat((m3 * inverse).into(), Mat4::IDENTITY);
+
+            let m2 = Mat2::new(Vec2::new(1.0, 3.0), Vec2::new(2.0, 4.0));
+            let inverse = m2.inverse().expect("the matrix is invertible");
+            assert_eq!(m2 * inverse, Mat2::IDENTITY);
+        }
+
+        #[test]
+        fn singular_matrices_have_no_inverse() {
+            let column = Vec4::new(1.0, 2.0, 3.0, 4.0);
+
+            assert!(Mat4::new(column, column * 2.0, Vec4::ZERO, Vec4::IDENTITY)
+                .inverse()
+                .is_none());
+            assert!(Mat3::new(column.truncate(), column.truncate(), Vec3::ZERO)
+                .inverse()
+                .is_none());
+            assert!(Mat2::new(Vec2::new(1.0, 2.0), Vec2::new(2.0, 4.0))
+                .inverse()
+                .is_none());
+            assert!(Mat4::<f32>::ZERO.inverse().is_none());
+        }
+
+        #[test]
+        fn look_at() {
+            let eye = Vec3::new(1.0, 2.0, 3.0);
+            let target = Vec3::new(4.0, 6.0, 3.0);
+            let view = Mat4::look_at(eye, target, Vec3::new(0.0, 0.0, 1.0));
+
+            assert_close(view * eye.extend(1.0), Vec4::new(0.0, 0.0, 0.0, 1.0));
+            // the target is 5 away straight ahead, down -z
+            assert_close(view * target.extend(1.0), Vec4::new(0.0, 0.0, -5.0, 1.0));
+            // up stays up and the view only rotates, so directions keep their length
+            assert_close(
+                view * Vec4::new(0.0, 0.0, 1.0, 0.0),
+                Vec4::new(0.0, 1.0, 0.0, 0.0),
+            );
+        }
+
+        #[test]
+        fn perspective() {
+            let (near, far) = (0.1, 100.0);
+            let projection = Mat4::perspective(FRAC_PI_2, 2.0, near, far);
+
+            assert!(project(projection, Vec3::new(0.0, 0.0, -near)).z.abs() < 1e-6);
+            assert!((project(projection, Vec3::new(0.0, 0.0, -far)).z - 1.0).abs() < 1e-6);
+
+            // a 90 degree field of view sees as far up as it looks ahead,
+            // and twice as far sideways
+            let corner = project(projection, Vec3::new(2.0, 1.0, -1.0));
+            assert!((corner.x - 1.0).abs() < 1e-6);
+            assert!((corner.y - 1.0).abs() < 1e-6);
+        }
+
+        #[test]
+        fn orthographic() {
+            let (near, far) = (0.5, 10.0);
+            let projection = Mat4::orthographic(-2.0, 4.0, -1.0, 1.0, near, far);
+
+            assert_close(
+                projection * Vec4::new(-2.0, -1.0, -near, 1.0),
+                Vec4::new(-1.0, -1.0, 0.0, 1.0),
+            );
+            assert_close(
+                projection * Vec4::new(4.0, 1.0, -far, 1.0),
+                Vec4::new(1.0, 1.0, 1.0, 1.0),
+            );
+        }
+    }
+}
+
+pub mod quaternion {
+    use super::matrix::{Mat3, Mat4};
+    use super::vector::{Vec3, Vec4};
+    use super::{Atan2, Identity, Sqrt, Trig, Zero};
+    use crate::util::{as_u8_slice, AsBytes};
+    use serde::{Deserialize, Serialize};
+    use std::ops::{Add, Div, Mul, MulAssign, Neg, Sub};
+
+    /// A quaternion stored as its vector part followed by its scalar part,
+    /// the same layout as a wgsl `vec4` with w as the scalar.
+    #[repr(C)]
+    #[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq)]
+    pub struct Quat<T = f32>(pub Vec3<T>, pub T);
+
+    impl<T: Zero + Identity> Identity for Quat<T> {
+        const IDENTITY: Self = Self(Vec3::ZERO, T::IDENTITY);
+    }
+
+    impl<'a, T> AsBytes<'a> for Quat<T> {
+        #[inline]
+        fn as_bytes(&'a self) -> &'a [u8] {
+            unsafe { as_u8_slice(self) }
+        }
+    }
+
+    impl<T: Add<Output = T>> Add for Quat<T> {
+        type Output = Self;
+
+        #[inline]
+        fn add(self, rhs: Self) -> Self::Output {
+            Self(self.0 + rhs.0, self.1 + rhs.1)
+        }
+    }
+
+    impl<T: Sub<Output = T>> Sub for Quat<T> {
+        type Output = Self;
+
+        #[inline]
+        fn sub(self, rhs: Self) -> Self::Output {
+            Self(self.0 - rhs.0, self.1 - rhs.1)
+        }
+    }
+
+    impl<T: Neg<Output = T>> Neg for Quat<T> {
+        type Output = Self;
+
+        #[inline]
+        fn neg(self) -> Self::Output {
+            Self(-self.0, -self.1)
+        }
+    }
+
+    impl<T: Mul<Output = T> + Copy> Mul<T> for Quat<T> {
+        type Output = Self;
+
+        #[inline]
+        fn mul(self, rhs: T) -> Self::Output {
+            Self(self.0 * rhs, self.1 * rhs)
+        }
+    }
+
+    impl<T: Div<Output = T> + Copy> Div<T> for Quat<T> {
+        type Output = Self;
+
+        #[inline]
+        fn div(self, rhs: T) -> Self::Output {
+            Self(self.0 / rhs, self.1 / rhs)
+        }
+    }
+
+    /// Hamilton product, `a * b` applies the rotation `b` first and then `a`.
+    impl<T: Add<Output = T> + Sub<Output = T> + Mul<Output = T> + Zero + Copy> Mul for Quat<T> {
+        type Output = Self;
+
+        #[inline]
+        fn mul(self, rhs: Self) -> Self::Output {
+            Self(
+                rhs.0 * self.1 + self.0 * rhs.1 + self.0.cross(rhs.0),
+                self.1 * rhs.1 - self.0.dot(rhs.0),
+            )
+        }
+    }
+
+    impl<T: Add<Output = T> + Sub<Output = T> + Mul<Output = T> + Zero + Copy> MulAssign for Quat<T> {
+        #[inline]
+        fn mul_assign(&mut self, rhs: Self) {
+            *self = *self * rhs;
+        }
+    }
+
+    /// Rotates the vector, the quaternion is expected to be normalized.
+    impl<T: Add<Output = T> + Sub<Output = T> + Mul<Output = T> + Zero + Identity + Copy>
+        Mul<Vec3<T>> for Quat<T>
+    {
+        type Output = Vec3<T>;
+
+        #[inline]
+        fn mul(self, rhs: Vec3<T>) -> Self::Output {
+            self.rotate(rhs)
+        }
+    }
+
+    impl<T: Add<Output = T> + Sub<Output = T> + Mul<Output = T> + Zero + Identity + Copy> Quat<T> {
+        #[inline]
+        pub fn dot(self, other: Self) -> T {
+            self.0.dot(other.0) + self.1 * other.1
+        }
+
+        /// Rotates `v`, the quaternion is expected to be normalized.
+        #[inline]
+        pub fn rotate(self, v: Vec3<T>) -> Vec3<T> {
+            let two = T::IDENTITY + T::IDENTITY;
+            let t = self.0.cross(v) * two;
+
+            v + t * self.1 + self.0.cross(t)
+        }
+
+        /// The rotation matrix of a normalized quaternion.
+        pub fn to_mat3(self) -> Mat3<T> {
+            let two = T::IDENTITY + T::IDENTITY;
+            let Self(Vec3 { x, y, z }, w) = self;
+
+            Mat3::new(
+                Vec3::new(
+                    T::IDENTITY - two * (y * y + z * z),
+                    two * (x * y + w * z),
+                    two * (x * z - w * y),
+                ),
+                Vec3::new(
+                    two * (x * y - w * z),
+                    T::IDENTITY - two * (x * x + z * z),
+                    two * (y * z + w * x),
+                ),
+                Vec3::new(
+                    two * (x * z + w * y),
+                    two * (y * z - w * x),
+                    T::IDENTITY - two * (x * x + y * y),
+                ),
+            )
+        }
+
+        /// The rotation matrix of a normalized quaternion.
+        pub fn to_mat4(self) -> Mat4<T> {
+            let Mat3 { x, y, z } = self.to_mat3();
+
+            Mat4::new(
+                Vec4::new(x.x, x.y, x.z, T::ZERO),
+                Vec4::new(y.x, y.y, y.z, T::ZERO),
+                Vec4::new(z.x, z.y, z.z, T::ZERO),
+                Vec4::new(T::ZERO, T::ZERO, T::ZERO, T::IDENTITY),
+            )
+        }
+    }
+
+    impl<T: Neg<Output = T>> Quat<T> {
+        #[inline]
+        pub fn conjugate(self) -> Self {
+            Self(-self.0, self.1)
+        }
+    }
+
+    impl<
+            T: Add<Output = T>
+                + Sub<Output = T>
+                + Mul<Output = T>
+                + Div<Output = T>
+                + Neg<Output = T>
+                + Identity
+                + Zero
+                + Sqrt
+                + PartialOrd
+                + Copy,
+        > Quat<T>
+    {
+        #[inline]
+        pub fn len(self) -> T {
+            self.dot(self).sqrt()
+        }
+
+        /// Returns the zero quaternion if the length is zero.
+        #[inline]
+        pub fn normalized(self) -> Self {
+            let len = self.len();
+
+            if len == T::ZERO {
+                Self(Vec3::ZERO, T::ZERO)
+            } else {
+                self / len
+            }
+        }
+
+        /// Returns `None` for the zero quaternion.
+        #[inline]
+        pub fn inverse(self) -> Option<Self> {
+            let len_squared = self.dot(self);
+
+            if len_squared == T::ZERO {
+                None
+            } else {
+                Some(self.conjugate() / len_squared)
+            }
+        }
+
+        /// Normalized linear interpolation, takes the shortest path.
+        pub fn nlerp(self, other: Self, t: T) -> Self {
+            let other = if self.dot(other) < T::ZERO {
+                -other
+            } else {
+                other
+            };
+
+            (self * (T::IDENTITY - t) + other * t).normalized()
+        }
+    }
+
+    impl<
+            T: Add<Output = T>
+                + Sub<Output = T>
+                + Mul<Output = T>
+                + Div<Output = T>
+                + Neg<Output = T>
+                + Identity
+                + Zero
+                + Sqrt
                 + Atan2
-                + Eq
+                + Trig
+                + PartialOrd
                 + Copy,
         > Quat<T>
     {
-        #[inline(always)]
-        fn to_axis_angle(self) -> Vec3<T> {
+        /// `axis` is expected to be normalized and `angle` is in radians.
+        pub fn from_axis_angle(axis: Vec3<T>, angle: T) -> Self {
+            let half = angle / (T::IDENTITY + T::IDENTITY);
+
+            Self(axis * half.sin(), half.cos())
+        }
+
+        /// Angles are in radians, the rotation about x is applied first, then y, then z.
+        pub fn from_euler(x: T, y: T, z: T) -> Self {
+            let two = T::IDENTITY + T::IDENTITY;
+            let (sx, cx) = ((x / two).sin(), (x / two).cos());
+            let (sy, cy) = ((y / two).sin(), (y / two).cos());
+            let (sz, cz) = ((z / two).sin(), (z / two).cos());
+
+            Self(
+                Vec3::new(
+                    sx * cy * cz - cx * sy * sz,
+                    cx * sy * cz + sx * cy * sz,
+                    cx * cy * sz - sx * sy * cz,
+                ),
+                cx * cy * cz + sx * sy * sz,
+            )
+        }
+
+        /// The rotation axis scaled by the rotation angle in radians.
+        #[inline]
+        pub fn to_axis_angle(self) -> Vec3<T> {
             let len = self.0.len();
 
             if len == T::ZERO {
@@ -401,49 +1538,630 @@ mod quaternion {
                 (self.0 / len) * (T::IDENTITY + T::IDENTITY) * len.atan2(self.1)
             }
         }
+
+        /// Spherical linear interpolation, takes the shortest path.
+        /// Both quaternions are expected to be normalized.
+        pub fn slerp(self, other: Self, t: T) -> Self {
+            let mut cos = self.dot(other);
+            let mut other = other;
+
+            if cos < T::ZERO {
+                cos = -cos;
+                other = -other;
+            }
+
+            let theta = cos.acos();
+            let sin = theta.sin();
+
+            // identical rotations, dividing by sin would produce nan
+            if sin <= T::ZERO || cos >= T::IDENTITY {
+                return self.nlerp(other, t);
+            }
+
+            (self * ((T::IDENTITY - t) * theta).sin() + other * (t * theta).sin()) / sin
+        }
     }
 
-    // struct Quat<T = f32> {
-    //     a: T,
-    //     b: T,
-    //     c: T,
-    //     d: T,
-    // }
+    impl<T: Add<Output = T> + Sub<Output = T> + Mul<Output = T> + Zero + Identity + Copy>
+        From<Quat<T>> for Mat3<T>
+    {
+        fn from(value: Quat<T>) -> Self {
+            value.to_mat3()
+        }
+    }
+
+    impl<T: Add<Output = T> + Sub<Output = T> + Mul<Output = T> + Zero + Identity + Copy>
+        From<Quat<T>> for Mat4<T>
+    {
+        fn from(value: Quat<T>) -> Self {
+            value.to_mat4()
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use std::f32::consts::FRAC_PI_2;
+
+        fn assert_close(actual: Vec3, expected: Vec3) {
+            assert!(
+                (actual - expected).len() < 1e-5,
+                "{actual:?} != {expected:?}"
+            );
+        }
+
+        fn assert_close_quat(actual: Quat, expected: Quat) {
+            assert_close(actual.0, expected.0);
+            assert!(
+                (actual.1 - expected.1).abs() < 1e-5,
+                "{actual:?} != {expected:?}"
+            );
+        }
+
+        fn rotations() -> Vec<Quat> {
+            vec![
+                Quat::from_axis_angle(Vec3::new(0.0, 0.0, 1.0), FRAC_PI_2),
+                Quat::from_axis_angle(Vec3::new(1.0, 2.0, 3.0).normalized(), 0.7),
+                Quat::from_euler(0.3, -1.2, 2.5),
+                Quat::IDENTITY,
+            ]
+        }
+
+        #[test]
+        fn rotation_matches_the_matrices() {
+            let v = Vec3::new(1.0, -2.0, 0.5);
+
+            assert_close(
+                Quat::from_axis_angle(Vec3::new(0.0, 0.0, 1.0), FRAC_PI_2)
+                    * Vec3::new(1.0, 0.0, 0.0),
+                Vec3::new(0.0, 1.0, 0.0),
+            );
+
+            for q in rotations() {
+                assert_close(q.rotate(v), q.to_mat3() * v);
+                assert_close((Mat4::from(q) * v.extend(1.0)).truncate(), q * v);
+            }
+        }
+
+        #[test]
+        fn inverse() {
+            for q in rotations() {
+                let inverse = q.inverse().expect("rotations are not zero");
+
+                assert_close_quat(q * inverse, Quat::IDENTITY);
+                assert_close_quat(inverse * q, Quat::IDENTITY);
+            }
+
+            // not normalized, the inverse still undoes it
+            let q = Quat(Vec3::new(1.0, 2.0, 3.0), 4.0);
+            assert_close_quat(q * q.inverse().unwrap(), Quat::IDENTITY);
+            assert!(Quat(Vec3::ZERO, 0.0).inverse().is_none());
+        }
+
+        #[test]
+        fn slerp() {
+            let a = Quat::from_axis_angle(Vec3::new(0.0, 1.0, 0.0), 0.2);
+            let b = Quat::from_euler(0.3, -1.2, 2.5);
+
+            assert_close_quat(a.slerp(b, 0.0), a);
+            assert_close_quat(a.slerp(b, 1.0), b);
+
+            let middle = a.slerp(b, 0.5);
+            assert!((middle.len() - 1.0).abs() < 1e-5);
+            // halfway along the arc is as far from either end
+            assert!((middle.dot(a) - middle.dot(b)).abs() < 1e-5);
+
+            // the shortest path is taken, -b is the same rotation as b
+            assert_close(
+                a.slerp(-b, 0.5) * Vec3::new(1.0, 0.0, 0.0),
+                middle * Vec3::new(1.0, 0.0, 0.0),
+            );
+
+            // identical rotations fall back to nlerp instead of dividing by zero
+            assert_close_quat(a.slerp(a, 0.5), a);
+        }
+    }
 }
 
-mod sdf {
+pub mod sdf {
     use super::vector::{Vec2, Vec3};
 
+    // Signed distance functions, negative inside. These mirror the wgsl versions
+    // so shader output can be checked on the cpu.
+
+    /// How overlapping contours of a path decide what is inside.
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub enum FillRule {
+        /// Inside where a ray crosses an odd number of edges.
+        EvenOdd,
+        /// Inside where the edges don't wind around the point zero times, what ttf outlines use.
+        NonZero,
+    }
+
+    #[inline]
+    pub fn union(a: f32, b: f32) -> f32 {
+        a.min(b)
+    }
+
+    #[inline]
+    pub fn intersection(a: f32, b: f32) -> f32 {
+        a.max(b)
+    }
+
+    /// `a` with `b` cut out of it.
+    #[inline]
+    pub fn subtraction(a: f32, b: f32) -> f32 {
+        a.max(-b)
+    }
+
+    /// Union blended over a distance of `k`.
+    #[inline]
+    pub fn smooth_union(a: f32, b: f32, k: f32) -> f32 {
+        if k <= 0.0 {
+            return union(a, b);
+        }
+
+        let h = (0.5 + 0.5 * (b - a) / k).clamp(0.0, 1.0);
+
+        b + (a - b) * h - k * h * (1.0 - h)
+    }
+
     impl Vec2 {
+        pub fn circle(self, center: Vec2, radius: f32) -> f32 {
+            (self - center).len() - radius
+        }
+
         pub fn rect(self, pos: Vec2, scale: Vec2) -> f32 {
             let d = Vec2::new((self.x - pos.x).abs(), (self.y - pos.y).abs()) - scale;
 
             (Vec2::new(d.x.max(0.0), d.y.max(0.0))).len() + d.x.max(d.y).min(0.0)
         }
 
-        pub fn polygon(self, points: Vec<Vec2>) -> f32 {
-            let d = self - points[0];
-            let mut d = d.dot(d);
-            let mut s = 1.0;
-            let mut j = points.len() - 1;
+        /// `scale` is the half size of the rect including the rounded corners.
+        pub fn rounded_rect(self, pos: Vec2, scale: Vec2, radius: f32) -> f32 {
+            let radius = radius.min(scale.x).min(scale.y);
+
+            self.rect(pos, scale - Vec2::new(radius, radius)) - radius
+        }
+
+        /// Unsigned distance to the line segment from `a` to `b`, same as `sdf_segment` in `font_renderer.wgsl`.
+        pub fn segment(self, a: Vec2, b: Vec2) -> f32 {
+            let pa = self - a;
+            let ba = b - a;
+            let len_squared = ba.dot(ba);
+
+            if len_squared == 0.0 {
+                return pa.len();
+            }
+
+            let h = (pa.dot(ba) / len_squared).clamp(0.0, 1.0);
+
+            (pa - ba * h).len()
+        }
+
+        /// Unsigned distance to the quadratic bezier with control points `a`, `b` and `c`,
+        /// same as `sdf_bezier` in `sdf_shader.wgsl`.
+        #[allow(clippy::many_single_char_names)]
+        pub fn quadratic_bezier(self, a: Vec2, b: Vec2, c: Vec2) -> f32 {
+            let e = b - a;
+            let f = a - b * 2.0 + c;
+
+            // the control points are colinear and evenly spaced, the curve is a line
+            if f.dot(f) == 0.0 {
+                return self.segment(a, c);
+            }
+
+            let g = e * 2.0;
+            let d = a - self;
+            let kk = 1.0 / f.dot(f);
+            let kx = kk * e.dot(f);
+            let ky = kk * (2.0 * e.dot(e) + d.dot(f)) / 3.0;
+            let kz = kk * d.dot(e);
+
+            let p = ky - kx * kx;
+            let p3 = p * p * p;
+            let q = kx * (2.0 * kx * kx - 3.0 * ky) + kz;
+            let h = q * q + 4.0 * p3;
+
+            let dist_squared = |t: f32| {
+                let v = d + (g + f * t) * t;
+                v.dot(v)
+            };
+
+            let res = if h >= 0.0 {
+                let h = h.sqrt();
+                let x = (Vec2::new(h, -h) - Vec2::new(q, q)) / 2.0;
+                let uv = Vec2::new(x.x.signum(), x.y.signum())
+                    * Vec2::new(x.x.abs().cbrt(), x.y.abs().cbrt());
+                let t = (uv.x + uv.y - kx).clamp(0.0, 1.0);
+
+                dist_squared(t)
+            } else {
+                let z = (-p).sqrt();
+                let v = (q / (p * z * 2.0)).acos() / 3.0;
+                let m = v.cos();
+                let n = v.sin() * 1.732_050_8;
+                let t = (Vec3::new(m + m, -n - m, n - m) * z - Vec3::new(kx, kx, kx))
+                    .clamp(Vec3::new(0.0, 0.0, 0.0), Vec3::new(1.0, 1.0, 1.0));
+
+                // the third root cannot be the closest
+                dist_squared(t.x).min(dist_squared(t.y))
+            };
+
+            res.sqrt()
+        }
+
+        /// Same as `sdf_quadratic_circle` in `sdf_shader.wgsl`.
+        #[allow(clippy::many_single_char_names)]
+        pub fn quadratic_circle(self, center: Vec2) -> f32 {
+            let mut p = (self - center).abs();
+
+            if p.y > p.x {
+                p = p.yx();
+            }
+
+            let a = p.x - p.y;
+            let b = p.x + p.y;
+            let c = (2.0 * b - 1.0) / 3.0;
+            let h = a * a + c * c * c;
+
+            let t = if h > 0.0 {
+                let h = h.sqrt();
+                (h - a).signum() * (h - a).abs().cbrt() - (h + a).cbrt()
+            } else {
+                let z = (-c).sqrt();
+                let v = (a / (c * z)).acos() / 3.0;
+                -z * (v.cos() + v.sin() * 1.732_050_8)
+            };
+
+            let t = t * 0.5;
+
+            let w = Vec2::new(-t, t) + Vec2::new(0.75 - t * t, 0.75 - t * t) - p;
+
+            w.len() * (a * a * 0.5 + b - 1.5).signum()
+        }
+
+        /// Arc of a circle with `radius` opening towards +y, covering `aperture` radians
+        /// to either side of the y axis, with a stroke `thickness` to either side of the arc.
+        pub fn arc(self, center: Vec2, aperture: f32, radius: f32, thickness: f32) -> f32 {
+            let p = self - center;
+            let p = Vec2::new(p.x.abs(), p.y);
+            let sc = Vec2::new(aperture.sin(), aperture.cos());
+
+            if sc.y * p.x > sc.x * p.y {
+                (p - sc * radius).len() - thickness
+            } else {
+                (p.len() - radius).abs() - thickness
+            }
+        }
+
+        /// Exact distance to an axis aligned ellipse, `radii` being the half axes.
+        #[allow(clippy::many_single_char_names)]
+        pub fn ellipse(self, center: Vec2, radii: Vec2) -> f32 {
+            if (radii.x - radii.y).abs() <= f32::EPSILON {
+                return self.circle(center, radii.x);
+            }
+
+            let mut p = (self - center).abs();
+            let mut ab = radii;
 
-            for i in 0..points.len() {
-                let e = points[j] - points[i];
-                let w = self - points[i];
-                let b = w - w.proj(e).limit_length(1.0);
-                d = d.min(b.dot(b));
-                let c = Vec3::new(
-                    self.y >= points[i].y,
-                    self.y < points[j].y,
-                    e.x * w.y > e.y * w.x,
-                );
+            if p.x > p.y {
+                p = p.yx();
+                ab = ab.yx();
+            }
 
-                if (c.x && c.y && c.z) || (!c.x && !c.y && !c.z) {
-                    s *= -1.0;
+            let l = ab.y * ab.y - ab.x * ab.x;
+            let m = ab.x * p.x / l;
+            let m2 = m * m;
+            let n = ab.y * p.y / l;
+            let n2 = n * n;
+            let c = (m2 + n2 - 1.0) / 3.0;
+            let c3 = c * c * c;
+            let q = c3 + m2 * n2 * 2.0;
+            let d = c3 + m2 * n2;
+            let g = m + m * n2;
+
+            let co = if d < 0.0 {
+                let h = (q / c3).acos() / 3.0;
+                let s = h.cos();
+                let t = h.sin() * 3f32.sqrt();
+                let rx = (-c * (s + t + 2.0) + m2).sqrt();
+                let ry = (-c * (s - t + 2.0) + m2).sqrt();
+
+                (ry + l.signum() * rx + g.abs() / (rx * ry) - m) / 2.0
+            } else {
+                let h = 2.0 * m * n * d.sqrt();
+                let s = (q + h).signum() * (q + h).abs().cbrt();
+                let u = (q - h).signum() * (q - h).abs().cbrt();
+                let rx = -s - u - c * 4.0 + 2.0 * m2;
+                let ry = (s - u) * 3f32.sqrt();
+                let rm = (rx * rx + ry * ry).sqrt();
+
+                // on the axis where all three roots meet the formula is 0 / 0,
+                // the closest point is the end of the axis
+                if rm == 0.0 {
+                    0.0
+                } else {
+                    (ry / (rm - rx).sqrt() + 2.0 * g / rm - m) / 2.0
                 }
+            };
+
+            let r = ab * Vec2::new(co, (1.0 - co * co).max(0.0).sqrt());
+
+            (r - p).len() * (p.y - r.y).signum()
+        }
+
+        pub fn triangle(self, a: Vec2, b: Vec2, c: Vec2) -> f32 {
+            let edges = [(a, b - a), (b, c - b), (c, a - c)];
+            let winding = (edges[0].1.x * edges[2].1.y - edges[0].1.y * edges[2].1.x).signum();
+
+            let (dist_squared, side) = edges
+                .iter()
+                .map(|&(start, edge)| {
+                    let v = self - start;
+                    let closest = v - edge * (v.dot(edge) / edge.dot(edge)).clamp(0.0, 1.0);
+
+                    (
+                        closest.dot(closest),
+                        winding * (v.x * edge.y - v.y * edge.x),
+                    )
+                })
+                .fold((f32::INFINITY, f32::INFINITY), |(d, s), (new_d, new_s)| {
+                    (d.min(new_d), s.min(new_s))
+                });
+
+            -dist_squared.sqrt() * side.signum()
+        }
+
+        /// Signed distance to a closed polygon, the last point connects back to the first.
+        /// An empty polygon is infinitely far away, so it disappears when it is unioned
+        /// with other shapes.
+        pub fn polygon(self, points: &[Vec2]) -> f32 {
+            self.path([points], FillRule::EvenOdd)
+        }
+
+        /// Signed distance to a shape made of closed contours, like a glyph outline.
+        /// Contours are closed implicitly and `fill_rule` decides which overlapping
+        /// regions count as inside.
+        pub fn path<'a>(
+            self,
+            contours: impl IntoIterator<Item = &'a [Vec2]>,
+            fill_rule: FillRule,
+        ) -> f32 {
+            let mut dist = f32::INFINITY;
+            let mut winding = 0i32;
+
+            for contour in contours {
+                let Some(&last) = contour.last() else {
+                    continue;
+                };
+
+                let mut a = last;
+
+                for &b in contour {
+                    dist = dist.min(self.segment(a, b));
+                    winding += self.crossing(a, b);
+                    a = b;
+                }
+            }
+
+            let inside = match fill_rule {
+                FillRule::EvenOdd => winding % 2 != 0,
+                FillRule::NonZero => winding != 0,
+            };
+
+            if inside {
+                -dist
+            } else {
+                dist
             }
+        }
+
+        // +1 if the edge crosses the horizontal line through self upwards with self on
+        // its left, -1 if it crosses downwards with self on its right
+        fn crossing(self, a: Vec2, b: Vec2) -> i32 {
+            let side = (b.x - a.x) * (self.y - a.y) - (self.x - a.x) * (b.y - a.y);
+
+            if a.y <= self.y && b.y > self.y && side > 0.0 {
+                1
+            } else if a.y > self.y && b.y <= self.y && side < 0.0 {
+                -1
+            } else {
+                0
+            }
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::math::Zero;
+        use std::f32::consts::FRAC_PI_4;
+
+        fn assert_close(actual: f32, expected: f32) {
+            assert!((actual - expected).abs() < 1e-4, "{actual} != {expected}");
+        }
+
+        #[test]
+        fn combinators() {
+            assert_close(union(1.0, -2.0), -2.0);
+            assert_close(intersection(1.0, -2.0), 1.0);
+            assert_close(subtraction(1.0, -2.0), 2.0);
+            assert_close(subtraction(-1.0, 2.0), -1.0);
+            assert_close(smooth_union(1.0, -2.0, 0.0), -2.0);
+            // shapes further apart than k are not blended
+            assert_close(smooth_union(0.0, 5.0, 1.0), 0.0);
+            assert_close(smooth_union(0.0, 0.0, 1.0), -0.25);
+        }
 
-            return s * d.sqrt();
+        #[test]
+        fn circle() {
+            let center = Vec2::new(1.0, 1.0);
+
+            assert_close(Vec2::new(1.0, 1.0).circle(center, 2.0), -2.0);
+            assert_close(Vec2::new(3.0, 1.0).circle(center, 2.0), 0.0);
+            assert_close(Vec2::new(4.0, 5.0).circle(center, 2.0), 3.0);
+        }
+
+        #[test]
+        fn rect() {
+            let scale = Vec2::new(2.0, 1.0);
+
+            assert_close(Vec2::new(0.0, 0.0).rect(Vec2::ZERO, scale), -1.0);
+            assert_close(Vec2::new(2.0, 0.5).rect(Vec2::ZERO, scale), 0.0);
+            assert_close(Vec2::new(5.0, 5.0).rect(Vec2::ZERO, scale), 5.0);
+        }
+
+        #[test]
+        fn rounded_rect() {
+            let scale = Vec2::new(2.0, 1.0);
+
+            assert_close(
+                Vec2::new(0.0, 0.0).rounded_rect(Vec2::ZERO, scale, 0.5),
+                -1.0,
+            );
+            assert_close(
+                Vec2::new(2.0, 0.0).rounded_rect(Vec2::ZERO, scale, 0.5),
+                0.0,
+            );
+            // measured from the center of the corner
+            assert_close(
+                Vec2::new(4.5, 4.5).rounded_rect(Vec2::ZERO, scale, 0.5),
+                4.5,
+            );
+        }
+
+        #[test]
+        fn segment() {
+            let (a, b) = (Vec2::new(0.0, 0.0), Vec2::new(2.0, 0.0));
+
+            assert_close(Vec2::new(1.0, 0.0).segment(a, b), 0.0);
+            assert_close(Vec2::new(1.0, -1.0).segment(a, b), 1.0);
+            assert_close(Vec2::new(5.0, 4.0).segment(a, b), 5.0);
+            assert_close(Vec2::new(3.0, 4.0).segment(a, a), 5.0);
+        }
+
+        #[test]
+        fn quadratic_bezier() {
+            let (a, b, c) = (
+                Vec2::new(0.0, 0.0),
+                Vec2::new(1.0, 2.0),
+                Vec2::new(2.0, 0.0),
+            );
+
+            // expected values are from sdf_bezier in sdf_shader.wgsl
+            assert_close(Vec2::new(1.0, 1.0).quadratic_bezier(a, b, c), 0.0);
+            assert_close(Vec2::new(1.0, 0.0).quadratic_bezier(a, b, c), 0.866_025_4);
+            assert_close(Vec2::new(0.0, 1.0).quadratic_bezier(a, b, c), 0.537_841_4);
+            assert_close(Vec2::new(3.0, 1.0).quadratic_bezier(a, b, c), 1.357_699_4);
+            assert_close(Vec2::new(1.0, 3.0).quadratic_bezier(a, b, c), 2.0);
+            assert_close(Vec2::new(-1.0, 0.0).quadratic_bezier(a, b, c), 1.0);
+            // evenly spaced control points fall back to a segment
+            assert_close(
+                Vec2::new(1.0, 1.0).quadratic_bezier(a, Vec2::new(1.0, 0.0), c),
+                1.0,
+            );
+        }
+
+        #[test]
+        fn quadratic_circle() {
+            assert_close(Vec2::new(0.0, 0.0).quadratic_circle(Vec2::ZERO), -1.0);
+            assert_close(Vec2::new(0.0, 1.0).quadratic_circle(Vec2::ZERO), 0.0);
+            assert_close(
+                Vec2::new(3.0, 0.0).quadratic_circle(Vec2::new(1.0, 0.0)),
+                1.0,
+            );
+        }
+
+        #[test]
+        fn arc() {
+            let center = Vec2::ZERO;
+
+            assert_close(Vec2::new(0.0, 1.0).arc(center, FRAC_PI_4, 1.0, 0.1), -0.1);
+            assert_close(Vec2::new(0.0, 1.1).arc(center, FRAC_PI_4, 1.0, 0.1), 0.0);
+            assert_close(Vec2::new(0.0, 0.0).arc(center, FRAC_PI_4, 1.0, 0.1), 0.9);
+            // past the ends the distance is to the nearest end
+            let end = Vec2::new(FRAC_PI_4.sin(), FRAC_PI_4.cos());
+            assert_close(
+                Vec2::new(0.0, -1.0).arc(center, FRAC_PI_4, 1.0, 0.1),
+                (Vec2::new(0.0, -1.0) - end).len() - 0.1,
+            );
+        }
+
+        #[test]
+        fn ellipse() {
+            let radii = Vec2::new(2.0, 1.0);
+
+            assert_close(Vec2::new(0.0, 0.0).ellipse(Vec2::ZERO, radii), -1.0);
+            assert_close(Vec2::new(2.0, 0.0).ellipse(Vec2::ZERO, radii), 0.0);
+            assert_close(Vec2::new(0.0, -1.0).ellipse(Vec2::ZERO, radii), 0.0);
+            assert_close(Vec2::new(4.0, 0.0).ellipse(Vec2::ZERO, radii), 2.0);
+            assert_close(Vec2::new(0.0, 3.0).ellipse(Vec2::ZERO, radii), 2.0);
+            assert_close(Vec2::new(0.0, 1.5).ellipse(Vec2::ZERO, radii.yx()), -0.5);
+            assert_close(Vec2::new(1.0, 1.0).ellipse(Vec2::ZERO, radii), 0.128_942_7);
+            assert_close(
+                Vec2::new(3.0, 4.0).ellipse(Vec2::ZERO, Vec2::new(1.0, 1.0)),
+                4.0,
+            );
+        }
+
+        #[test]
+        fn triangle() {
+            let (a, b, c) = (
+                Vec2::new(0.0, 0.0),
+                Vec2::new(4.0, 0.0),
+                Vec2::new(0.0, 3.0),
+            );
+
+            assert_close(Vec2::new(1.0, 1.0).triangle(a, b, c), -1.0);
+            assert_close(Vec2::new(2.0, 0.0).triangle(a, b, c), 0.0);
+            assert_close(Vec2::new(0.0, -2.0).triangle(a, b, c), 2.0);
+            // the winding of the corners does not matter
+            assert_close(Vec2::new(1.0, 1.0).triangle(a, c, b), -1.0);
+            assert_close(Vec2::new(0.0, -2.0).triangle(a, c, b), 2.0);
+        }
+
+        #[test]
+        fn polygon() {
+            let square = [
+                Vec2::new(0.0, 0.0),
+                Vec2::new(2.0, 0.0),
+                Vec2::new(2.0, 2.0),
+                Vec2::new(0.0, 2.0),
+            ];
+
+            assert_close(Vec2::new(1.0, 1.0).polygon(&square), -1.0);
+            assert_close(Vec2::new(1.0, 0.0).polygon(&square), 0.0);
+            assert_close(Vec2::new(3.0, 1.0).polygon(&square), 1.0);
+            assert!(Vec2::new(1.0, 1.0).polygon(&[]).is_infinite());
+            assert_close(union(Vec2::new(1.0, 1.0).polygon(&[]), 2.0), 2.0);
+        }
+
+        #[test]
+        fn path_fill_rules() {
+            let outer = [
+                Vec2::new(0.0, 0.0),
+                Vec2::new(4.0, 0.0),
+                Vec2::new(4.0, 4.0),
+                Vec2::new(0.0, 4.0),
+            ];
+            let inner = [
+                Vec2::new(1.0, 1.0),
+                Vec2::new(3.0, 1.0),
+                Vec2::new(3.0, 3.0),
+                Vec2::new(1.0, 3.0),
+            ];
+            let reversed: Vec<_> = inner.iter().rev().copied().collect();
+            let center = Vec2::new(2.0, 2.0);
+
+            // the inner square winds the same way, so it is only a hole with even odd
+            assert_close(center.path([&outer[..], &inner], FillRule::NonZero), -1.0);
+            assert_close(center.path([&outer[..], &inner], FillRule::EvenOdd), 1.0);
+            assert_close(center.path([&outer[..], &reversed], FillRule::NonZero), 1.0);
+            assert_close(
+                Vec2::new(0.5, 2.0).path([&outer[..], &inner], FillRule::EvenOdd),
+                -0.5,
+            );
         }
     }
 }
@@ -455,3 +2173,268 @@ fn scratch() {
     let v = Vec4::IDENTITY;
     v.limit_length(0.5);
 }
+
+// the expected values are exact in binary so they are compared exactly
+#[cfg(test)]
+#[allow(clippy::float_cmp)]
+mod tests {
+    use super::vector::{Vec2, Vec3, Vec4};
+    use super::Zero;
+
+    /// Vectors with components in -10..10 from a fixed xorshift sequence, so
+    /// failures are reproducible.
+    fn random_vectors(count: usize) -> Vec<Vec3> {
+        let mut state = 0x9e37_79b9_u32;
+        let mut next = move || {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            #[allow(clippy::cast_precision_loss)]
+            let unit = (state >> 8) as f32 / (1 << 24) as f32;
+            unit * 20.0 - 10.0
+        };
+
+        (0..count)
+            .map(|_| Vec3::new(next(), next(), next()))
+            .collect()
+    }
+
+    /// Triples of random vectors.
+    fn random_triples() -> impl Iterator<Item = (Vec3, Vec3, Vec3)> {
+        let vectors = random_vectors(300);
+
+        (0..vectors.len() / 3)
+            .map(move |i| (vectors[i * 3], vectors[i * 3 + 1], vectors[i * 3 + 2]))
+    }
+
+    fn assert_close(actual: f32, expected: f32, scale: f32) {
+        assert!(
+            (actual - expected).abs() <= 1e-5 * scale.max(1.0),
+            "{actual} != {expected}"
+        );
+    }
+
+    fn assert_close_vec(actual: Vec3, expected: Vec3, scale: f32) {
+        assert!(
+            (actual - expected).len() <= 1e-5 * scale.max(1.0),
+            "{actual:?} != {expected:?}"
+        );
+    }
+
+    #[test]
+    fn commutativity() {
+        for (a, b, _) in random_triples() {
+            assert_eq!(a + b, b + a);
+            assert_eq!(a * b, b * a);
+            assert_eq!(a.dot(b), b.dot(a));
+            assert_eq!(a.cross(b), -b.cross(a));
+        }
+    }
+
+    #[test]
+    fn distributivity() {
+        for (a, b, c) in random_triples() {
+            let scale = a.len() * (b.len() + c.len());
+
+            assert_close_vec(a * (b + c), a * b + a * c, scale);
+            assert_close_vec((b + c) * 3.0, b * 3.0 + c * 3.0, scale);
+            assert_close(a.dot(b + c), a.dot(b) + a.dot(c), scale);
+            assert_close_vec(a.cross(b + c), a.cross(b) + a.cross(c), scale);
+        }
+    }
+
+    #[test]
+    fn normalized_vectors_have_unit_length() {
+        for v in random_vectors(100) {
+            assert_close(v.normalized().len(), 1.0, 1.0);
+            assert_close(v.xy().normalized().len(), 1.0, 1.0);
+            assert_close(v.extend(1.0).normalized().len(), 1.0, 1.0);
+            // the direction is kept
+            assert_close_vec(v.normalized() * v.len(), v, v.len());
+        }
+    }
+
+    #[test]
+    fn dot_and_cross_identities() {
+        for (a, b, c) in random_triples() {
+            let cross = a.cross(b);
+            let scale = a.len() * b.len();
+
+            // the cross product is perpendicular to both inputs
+            assert_close(cross.dot(a), 0.0, scale * a.len());
+            assert_close(cross.dot(b), 0.0, scale * b.len());
+            assert_eq!(a.cross(a), Vec3::ZERO);
+            assert_close(a.dot(a), a.len() * a.len(), a.dot(a));
+
+            // lagrange's identity, |a x b|^2 + (a . b)^2 = |a|^2 |b|^2
+            assert_close(
+                cross.dot(cross) + a.dot(b) * a.dot(b),
+                scale * scale,
+                scale * scale,
+            );
+
+            // the scalar triple product is the same for every rotation of the inputs
+            let triple = a.dot(b.cross(c));
+            assert_close(triple, b.dot(c.cross(a)), scale * c.len());
+            assert_close(triple, c.dot(a.cross(b)), scale * c.len());
+        }
+    }
+
+    #[test]
+    fn operators() {
+        let a = Vec3::new(1.0, 2.0, 3.0);
+        let b = Vec3::new(4.0, 5.0, 6.0);
+
+        assert_eq!(a + b, Vec3::new(5.0, 7.0, 9.0));
+        assert_eq!(b - a, Vec3::new(3.0, 3.0, 3.0));
+        assert_eq!(a * b, Vec3::new(4.0, 10.0, 18.0));
+        assert_eq!(b / a, Vec3::new(4.0, 2.5, 2.0));
+        assert_eq!(a * 2.0, Vec3::new(2.0, 4.0, 6.0));
+        assert_eq!(a / 2.0, Vec3::new(0.5, 1.0, 1.5));
+        assert_eq!(-a, Vec3::new(-1.0, -2.0, -3.0));
+    }
+
+    #[test]
+    fn assign_operators() {
+        let mut v = Vec2::new(1.0, 2.0);
+
+        v += Vec2::new(1.0, 1.0);
+        assert_eq!(v, Vec2::new(2.0, 3.0));
+        v -= Vec2::new(1.0, 2.0);
+        assert_eq!(v, Vec2::new(1.0, 1.0));
+        v *= Vec2::new(3.0, 4.0);
+        assert_eq!(v, Vec2::new(3.0, 4.0));
+        v /= Vec2::new(3.0, 2.0);
+        assert_eq!(v, Vec2::new(1.0, 2.0));
+        v *= 4.0;
+        assert_eq!(v, Vec2::new(4.0, 8.0));
+        v /= 2.0;
+        assert_eq!(v, Vec2::new(2.0, 4.0));
+    }
+
+    #[test]
+    fn products() {
+        let a = Vec4::new(1.0, 2.0, 3.0, 4.0);
+
+        assert_eq!(a.sum(), 10.0);
+        assert_eq!(a.dot(Vec4::new(1.0, 0.0, 1.0, 0.0)), 4.0);
+        assert_eq!(Vec2::new(3.0, 4.0).len(), 5.0);
+        assert_eq!(
+            Vec3::new(1.0, 0.0, 0.0).cross(Vec3::new(0.0, 1.0, 0.0)),
+            Vec3::new(0.0, 0.0, 1.0)
+        );
+        assert_eq!(
+            Vec2::new(2.0, 3.0).proj(Vec2::new(4.0, 0.0)),
+            Vec2::new(2.0, 0.0)
+        );
+    }
+
+    #[test]
+    fn normalized() {
+        assert_eq!(Vec2::new(3.0, 4.0).normalized(), Vec2::new(0.6, 0.8));
+        assert!((Vec3::new(1.0_f32, -2.0, 2.0).normalized().len() - 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn limit_length() {
+        assert_eq!(Vec2::new(3.0, 4.0).limit_length(2.5), Vec2::new(1.5, 2.0));
+        assert_eq!(Vec2::new(3.0, 4.0).limit_length(10.0), Vec2::new(3.0, 4.0));
+    }
+
+    #[test]
+    fn zero_length() {
+        let zero = Vec2::new(0.0, 0.0);
+
+        assert_eq!(zero.len(), 0.0);
+        assert_eq!(zero.normalized(), zero);
+        assert_eq!(zero.limit_length(1.0), zero);
+        assert_eq!(Vec2::new(1.0, 2.0).proj(zero), zero);
+    }
+
+    #[test]
+    fn swizzles() {
+        let v = Vec4::new(1.0, 2.0, 3.0, 4.0);
+
+        assert_eq!(v.xy(), Vec2::new(1.0, 2.0));
+        assert_eq!(v.wzyx(), Vec4::new(4.0, 3.0, 2.0, 1.0));
+        assert_eq!(v.zxz(), Vec3::new(3.0, 1.0, 3.0));
+        assert_eq!(Vec2::new(5.0, 6.0).yyxx(), Vec4::new(6.0, 6.0, 5.0, 5.0));
+        assert_eq!(Vec3::new(1, 2, 3).zyx(), Vec3::new(3, 2, 1));
+    }
+
+    #[test]
+    fn lerp_and_clamp() {
+        let a = Vec2::new(0.0, 10.0);
+        let b = Vec2::new(4.0, -10.0);
+
+        assert_eq!(a.lerp(b, 0.0), a);
+        assert_eq!(a.lerp(b, 1.0), b);
+        assert_eq!(a.lerp(b, 0.25), Vec2::new(1.0, 5.0));
+        // not clamped, like wgsl's mix
+        assert_eq!(a.lerp(b, 2.0), Vec2::new(8.0, -30.0));
+
+        let low = Vec3::new(0.0, 0.0, 0.0);
+        let high = Vec3::new(1.0, 1.0, 1.0);
+        assert_eq!(
+            Vec3::new(-1.0, 0.5, 2.0).clamp(low, high),
+            Vec3::new(0.0, 0.5, 1.0)
+        );
+        assert_eq!(
+            Vec2::new(1.0, 5.0).min(Vec2::new(2.0, 3.0)),
+            Vec2::new(1.0, 3.0)
+        );
+        assert_eq!(
+            Vec2::new(1.0, 5.0).max(Vec2::new(2.0, 3.0)),
+            Vec2::new(2.0, 5.0)
+        );
+        assert_eq!(Vec2::new(-1.5, 2.25).floor(), Vec2::new(-2.0, 2.0));
+        assert_eq!(Vec2::new(-1.5, 2.25).fract(), Vec2::new(0.5, 0.25));
+        assert_eq!(Vec2::new(-1.5, 2.25).abs(), Vec2::new(1.5, 2.25));
+    }
+
+    #[test]
+    fn step_and_smoothstep() {
+        let edge = Vec2::new(1.0, 1.0);
+
+        assert_eq!(Vec2::new(0.5, 1.0).step(edge), Vec2::new(0.0, 1.0));
+
+        let low = Vec2::new(0.0, 0.0);
+        let high = Vec2::new(2.0, 2.0);
+        assert_eq!(
+            Vec2::new(-1.0, 3.0).smoothstep(low, high),
+            Vec2::new(0.0, 1.0)
+        );
+        assert_eq!(
+            Vec2::new(1.0, 0.5).smoothstep(low, high),
+            Vec2::new(0.5, 0.15625)
+        );
+    }
+
+    #[test]
+    fn smoothstep_with_equal_edges_is_a_step() {
+        let edge = Vec3::new(1.0, 1.0, 0.0);
+        let high = Vec3::new(1.0, 1.0, 2.0);
+        let v = Vec3::new(0.5, 1.0, 1.0).smoothstep(edge, high);
+
+        assert_eq!(v, Vec3::new(0.0, 1.0, 0.5));
+        // used to divide zero by zero
+        assert_eq!(
+            Vec2::new(0.0, -1.0).smoothstep(Vec2::ZERO, Vec2::ZERO),
+            Vec2::new(1.0, 0.0)
+        );
+    }
+
+    #[test]
+    fn integers() {
+        assert_eq!(Vec2::new(3, 4) + Vec2::new(1, 1), Vec2::new(4, 5));
+        assert_eq!(Vec2::new(7, -7) / 2, Vec2::new(3, -3));
+        assert_eq!(Vec2::new(3, 4).len(), 5);
+        // isqrt rounds the length down
+        assert_eq!(Vec2::new(1, 1).len(), 1);
+        // the division truncates
+        assert_eq!(Vec2::new(3, 4).normalized(), Vec2::new(0, 0));
+        assert_eq!(Vec2::new(0, 5).normalized(), Vec2::new(0, 1));
+        assert_eq!(Vec2::new(3, 4).limit_length(2), Vec2::new(1, 1));
+        assert_eq!(Vec2::new(0, 0).normalized(), Vec2::new(0, 0));
+    }
+}
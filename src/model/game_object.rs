@@ -1,7 +1,60 @@
-use glm::Vec3;
+use crate::math::{matrix::Mat4, vector::Vec3};
 
 #[repr(C)]
 pub struct Object {
     pub position: Vec3,
     pub angle: f32,
 }
+
+impl Object {
+    /// Rotation about the z axis followed by the translation, in that order of multiplication.
+    pub fn transform(&self) -> Mat4 {
+        Mat4::from_axis_angle(Vec3::new(0.0, 0.0, 1.0), self.angle)
+            * Mat4::from_translation(self.position)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::vector::Vec4;
+    use std::f32::consts::FRAC_PI_2;
+
+    fn assert_close(a: Vec4, b: Vec4) {
+        assert!((a - b).len() < 1e-5, "{a:?} != {b:?}");
+    }
+
+    #[test]
+    fn transform_translates_then_rotates() {
+        let object = Object {
+            position: Vec3::new(1.0, 2.0, 3.0),
+            angle: FRAC_PI_2,
+        };
+
+        // (1, 1, 1) moved to (2, 3, 4) and turned a quarter counter clockwise
+        assert_close(
+            object.transform() * Vec4::new(1.0, 1.0, 1.0, 1.0),
+            Vec4::new(-3.0, 2.0, 4.0, 1.0),
+        );
+    }
+
+    #[test]
+    fn transform_composes_with_scale() {
+        let object = Object {
+            position: Vec3::new(1.0, 2.0, 3.0),
+            angle: FRAC_PI_2,
+        };
+        let model = object.transform() * Mat4::from_scale(Vec3::new(2.0, 3.0, 4.0));
+
+        // scaled to (2, 3, 4), moved to (3, 5, 7) and then turned
+        assert_close(
+            model * Vec4::new(1.0, 1.0, 1.0, 1.0),
+            Vec4::new(-5.0, 3.0, 7.0, 1.0),
+        );
+        // directions are scaled and turned but not moved
+        assert_close(
+            model * Vec4::new(1.0, 0.0, 0.0, 0.0),
+            Vec4::new(0.0, 2.0, 0.0, 0.0),
+        );
+    }
+}
@@ -1,4 +1,4 @@
-use glm::*;
+use crate::math::vector::Vec3;
 use wgpu::util::DeviceExt;
 
 #[repr(C)]
@@ -28,16 +28,16 @@ impl Vertex {
 pub fn make_triangle(device: &wgpu::Device) -> wgpu::Buffer {
     let vertices: [Vertex; 3] = [
         Vertex {
-            position: vec3(-0.75, -0.75, 0.0),
-            color: vec3(0.0, 1.0, 0.0),
+            position: Vec3::new(-0.75, -0.75, 0.0),
+            color: Vec3::new(0.0, 1.0, 0.0),
         },
         Vertex {
-            position: vec3(0.75, -0.75, 0.0),
-            color: vec3(1.0, 1.0, 0.0),
+            position: Vec3::new(0.75, -0.75, 0.0),
+            color: Vec3::new(1.0, 1.0, 0.0),
         },
         Vertex {
-            position: vec3(0.0, 0.75, 0.0),
-            color: vec3(0.5, 0.0, 1.0),
+            position: Vec3::new(0.0, 0.75, 0.0),
+            color: Vec3::new(0.5, 0.0, 1.0),
         },
     ];
 
@@ -53,20 +53,20 @@ pub fn make_triangle(device: &wgpu::Device) -> wgpu::Buffer {
 pub fn make_quad(device: &wgpu::Device) -> Mesh {
     let vertices: [Vertex; 4] = [
         Vertex {
-            position: vec3(-1.0, -1.0, 0.0),
-            color: vec3(0.0, 1.0, 0.0),
+            position: Vec3::new(-1.0, -1.0, 0.0),
+            color: Vec3::new(0.0, 1.0, 0.0),
         },
         Vertex {
-            position: vec3(1.0, -1.0, 0.0),
-            color: vec3(1.0, 1.0, 0.0),
+            position: Vec3::new(1.0, -1.0, 0.0),
+            color: Vec3::new(1.0, 1.0, 0.0),
         },
         Vertex {
-            position: vec3(1.0, 1.0, 0.0),
-            color: vec3(1.0, 0.0, 0.0),
+            position: Vec3::new(1.0, 1.0, 0.0),
+            color: Vec3::new(1.0, 0.0, 0.0),
         },
         Vertex {
-            position: vec3(-1.0, 1.0, 0.0),
-            color: vec3(0.0, 0.0, 0.0),
+            position: Vec3::new(-1.0, 1.0, 0.0),
+            color: Vec3::new(0.0, 0.0, 0.0),
         },
     ];
 
@@ -1,4 +1,5 @@
 use super::bind_group;
+use crate::math::matrix::Mat4;
 use crate::util::as_u8_slice;
 
 #[repr(C)]
@@ -10,10 +11,8 @@ pub struct UBO {
 
 impl UBO {
     pub fn new(device: &wgpu::Device, object_count: usize, layout: wgpu::BindGroupLayout) -> Self {
-        let allignment = glm::max(
-            device.limits().min_uniform_buffer_offset_alignment as u32,
-            std::mem::size_of::<glm::Mat4>() as u32,
-        ) as u64;
+        let allignment = u64::from(device.limits().min_uniform_buffer_offset_alignment)
+            .max(std::mem::size_of::<Mat4>() as u64);
 
         let buffer_descriptor = wgpu::BufferDescriptor {
             label: Some("UBO"),
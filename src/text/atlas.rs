@@ -0,0 +1,438 @@
+use super::font::{Font, GlyphId};
+use super::packer::ShelfPacker;
+use super::{Atlas, AtlasData, CharData};
+use crate::math::vector::Vec2;
+use image::GenericImage;
+
+/// A single channel signed distance field of one glyph.
+/// Values above 128 are inside the glyph and 128 is the edge.
+#[derive(Debug, Clone)]
+pub struct GlyphBitmap {
+    pub width: u32,
+    pub height: u32,
+    /// Rows from top to bottom.
+    pub pixels: Vec<u8>,
+    /// Bottom left corner of the bitmap in pixels relative to the pen position, y up.
+    pub offset: Vec2,
+}
+
+impl GlyphBitmap {
+    /// White pixels with the distance field in alpha, how atlases store it.
+    pub fn to_rgba(&self) -> image::RgbaImage {
+        image::RgbaImage::from_fn(self.width, self.height, |x, y| {
+            image::Rgba([255, 255, 255, self.pixels[(y * self.width + x) as usize]])
+        })
+    }
+
+    /// Rasterizes `glyph` at `font_size` pixels per em. `spread` is the distance in pixels
+    /// that maps to the full 0 to 255 range on each side of the edge, `padding` is added
+    /// around the outline so the field has room to fall off.
+    /// Glyphs without an outline give an empty bitmap.
+    #[allow(
+        clippy::cast_possible_truncation,
+        clippy::cast_sign_loss,
+        clippy::cast_precision_loss
+    )]
+    pub fn sdf(font: &Font, glyph: GlyphId, font_size: f32, spread: f32, padding: u32) -> Self {
+        let scale = font_size / font.units_per_em();
+
+        // a twentieth of a pixel is well below what the 8 bit field can show
+        let Some(outline) = font.outline(glyph, scale, 0.05) else {
+            return Self {
+                width: 0,
+                height: 0,
+                pixels: Vec::new(),
+                offset: Vec2::new(0.0, 0.0),
+            };
+        };
+
+        let min = outline.bounds.min.floor() - Vec2::new(padding as f32, padding as f32);
+        let max = Vec2::new(outline.bounds.max.x.ceil(), outline.bounds.max.y.ceil())
+            + Vec2::new(padding as f32, padding as f32);
+        let width = (max.x - min.x) as u32;
+        let height = (max.y - min.y) as u32;
+
+        let mut pixels = Vec::with_capacity((width * height) as usize);
+
+        for row in 0..height {
+            for column in 0..width {
+                // sample at the pixel center, rows go down while the outline goes up
+                let point = min + Vec2::new(column as f32 + 0.5, (height - row) as f32 - 0.5);
+                let distance = outline.distance(point);
+                let value = (0.5 - 0.5 * distance / spread).clamp(0.0, 1.0);
+
+                pixels.push((value * 255.0).round() as u8);
+            }
+        }
+
+        Self {
+            width,
+            height,
+            pixels,
+            offset: min,
+        }
+    }
+}
+
+/// Where a glyph bitmap ended up in an atlas.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PlacedGlyph {
+    /// Top left corner in pixels.
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+    /// Bottom left corner of the bitmap in pixels relative to the pen position, y up.
+    pub offset: Vec2,
+}
+
+/// Builds a signed distance field font atlas from a font at runtime.
+/// The atlas keeps the font, so `Line`s in it are shaped.
+pub struct Builder<'a> {
+    font: &'a Font,
+    font_size: f32,
+    spread: f32,
+    padding: u32,
+    width: u32,
+    chars: Vec<char>,
+}
+
+impl<'a> Builder<'a> {
+    /// Defaults to printable ascii at 64 pixels per em in a 1024 pixel wide atlas.
+    pub fn new(font: &'a Font) -> Self {
+        Self {
+            font,
+            font_size: 64.0,
+            spread: 8.0,
+            padding: 8,
+            width: 1024,
+            chars: (' '..='~').collect(),
+        }
+    }
+
+    pub fn add_font_size(self, font_size: f32) -> Self {
+        Self { font_size, ..self }
+    }
+
+    /// Distance in pixels from the edge to where the field saturates.
+    pub fn add_spread(self, spread: f32) -> Self {
+        Self { spread, ..self }
+    }
+
+    /// Empty pixels around each glyph, should be at least the spread.
+    pub fn add_padding(self, padding: u32) -> Self {
+        Self { padding, ..self }
+    }
+
+    pub fn add_width(self, width: u32) -> Self {
+        Self { width, ..self }
+    }
+
+    /// Replaces the characters to put in the atlas.
+    pub fn add_chars(self, chars: impl IntoIterator<Item = char>) -> Self {
+        Self {
+            chars: chars.into_iter().collect(),
+            ..self
+        }
+    }
+
+    /// Characters missing from the font or too wide for the atlas are left out.
+    /// The atlas is as tall as it needs to be, rounded up to a power of two.
+    #[allow(clippy::cast_precision_loss)]
+    pub fn build(self) -> Atlas {
+        let scale = self.font_size / self.font.units_per_em();
+        let (ascender, _, _) = self.font.vertical_metrics();
+        let ascender = ascender * scale;
+
+        let mut glyphs: Vec<_> = self
+            .chars
+            .iter()
+            .filter_map(|&c| Some((c, self.font.glyph_id(c)?)))
+            .map(|(c, glyph)| {
+                let bitmap =
+                    GlyphBitmap::sdf(self.font, glyph, self.font_size, self.spread, self.padding);
+                let advance = self.font.advance(glyph) * scale;
+
+                (c, glyph, bitmap, advance)
+            })
+            .collect();
+
+        // packing the tallest first keeps the shelves full
+        glyphs.sort_by_key(|(_, _, bitmap, _)| std::cmp::Reverse(bitmap.height));
+
+        let mut packer = ShelfPacker::new(self.width, u32::MAX);
+
+        let placed: Vec<_> = glyphs
+            .into_iter()
+            .filter_map(|(c, glyph, bitmap, advance)| {
+                let position = packer.allocate(bitmap.width, bitmap.height)?;
+                Some((c, glyph, bitmap, advance, position))
+            })
+            .collect();
+
+        let height = packer.used_height().max(1).next_power_of_two();
+
+        let mut image =
+            image::RgbaImage::from_pixel(self.width, height, image::Rgba([255, 255, 255, 0]));
+
+        for (_, _, bitmap, _, (x, y)) in &placed {
+            image
+                .copy_from(&bitmap.to_rgba(), *x, *y)
+                .expect("glyphs are placed inside the atlas");
+        }
+
+        let size = Vec2::new(self.width as f32, height as f32);
+
+        let mut char_data = std::collections::HashMap::new();
+        let mut glyph_data = std::collections::HashMap::new();
+
+        for (c, glyph, bitmap, advance, (x, y)) in placed {
+            let placed = PlacedGlyph {
+                x,
+                y,
+                width: bitmap.width,
+                height: bitmap.height,
+                offset: bitmap.offset,
+            };
+            let data = CharData::from_pixels(placed, ascender, advance, size);
+
+            char_data.insert(c.to_string(), data);
+            glyph_data.insert(glyph, data);
+        }
+
+        Atlas {
+            image,
+            encoding: Encoding::DistanceField,
+            data: AtlasData {
+                size,
+                font_size: self.font_size,
+                char_data,
+            },
+            font: Some(self.font.clone()),
+            glyphs: glyph_data,
+        }
+    }
+}
+
+/// What the glyph channel of an atlas holds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Encoding {
+    /// How much of each pixel the glyph covers, what rTexPacker exports.
+    /// Drawn as is, so it blurs when scaled up.
+    Coverage,
+    /// A signed distance field with the edge at 0.5, what `Builder` and `GlyphCache` make.
+    /// Thresholded so edges stay sharp at any size.
+    #[default]
+    DistanceField,
+}
+
+/// How atlas pixels are stored on the gpu.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Format {
+    /// Only the glyph channel, a quarter of the memory of `Rgba8`.
+    R8,
+    /// The glyph in alpha with room for colour, for multi-channel fields and emoji.
+    #[default]
+    Rgba8,
+}
+
+impl Format {
+    pub fn texture_format(self) -> wgpu::TextureFormat {
+        match self {
+            Format::R8 => wgpu::TextureFormat::R8Unorm,
+            Format::Rgba8 => wgpu::TextureFormat::Rgba8UnormSrgb,
+        }
+    }
+
+    /// The atlas format of a texture, `None` for formats atlases are not stored in.
+    pub fn of(format: wgpu::TextureFormat) -> Option<Self> {
+        match format {
+            wgpu::TextureFormat::R8Unorm => Some(Format::R8),
+            wgpu::TextureFormat::Rgba8Unorm | wgpu::TextureFormat::Rgba8UnormSrgb => {
+                Some(Format::Rgba8)
+            }
+            _ => None,
+        }
+    }
+
+    pub fn bytes_per_pixel(self) -> u32 {
+        match self {
+            Format::R8 => 1,
+            Format::Rgba8 => 4,
+        }
+    }
+
+    /// The channel `uv_tris.wgsl` reads the glyph from.
+    pub fn glyph_channel(self) -> u32 {
+        match self {
+            Format::R8 => 0,
+            Format::Rgba8 => 3,
+        }
+    }
+}
+
+/// Writes `image` into `texture` with its top left corner at `x`, `y`,
+/// converted to whichever `Format` the texture has.
+///
+/// # Panics
+/// When the texture is not in a `Format`.
+pub fn write_texture(
+    queue: &wgpu::Queue,
+    texture: &wgpu::Texture,
+    x: u32,
+    y: u32,
+    image: &image::RgbaImage,
+) {
+    let format = Format::of(texture.format()).expect("atlas textures are R8 or RGBA8");
+
+    let bytes: std::borrow::Cow<[u8]> = match format {
+        Format::R8 => image.pixels().map(|pixel| pixel.0[3]).collect(),
+        Format::Rgba8 => image.as_raw().into(),
+    };
+
+    queue.write_texture(
+        wgpu::TexelCopyTextureInfo {
+            texture,
+            mip_level: 0,
+            origin: wgpu::Origin3d { x, y, z: 0 },
+            aspect: wgpu::TextureAspect::All,
+        },
+        &bytes,
+        wgpu::TexelCopyBufferLayout {
+            offset: 0,
+            bytes_per_row: Some(image.width() * format.bytes_per_pixel()),
+            rows_per_image: Some(image.height()),
+        },
+        wgpu::Extent3d {
+            width: image.width(),
+            height: image.height(),
+            depth_or_array_layers: 1,
+        },
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[allow(clippy::cast_possible_truncation)]
+    fn create_texture_round_trips() {
+        let Ok((_instance, device, queue)) = crate::util::get_gpu() else {
+            eprintln!("no gpu adapter, skipping");
+            return;
+        };
+
+        // rows of 300 R8 pixels are not a multiple of 256 bytes
+        let image = image::RgbaImage::from_fn(300, 17, |x, y| {
+            image::Rgba([x as u8, y as u8, (x + y) as u8, (x * 7 + y * 13) as u8])
+        });
+        let mut atlas = Atlas::empty();
+        atlas.image = image.clone();
+
+        for format in [Format::R8, Format::Rgba8] {
+            let texture = atlas.create_texture(&device, &queue, format);
+            let rows = crate::util::read_texture(&device, &queue, &texture);
+
+            assert_eq!(rows.len(), 17);
+
+            for (y, row) in rows.iter().enumerate() {
+                let expected: Vec<u8> = image
+                    .rows()
+                    .nth(y)
+                    .expect("the image has the row")
+                    .flat_map(|pixel| match format {
+                        Format::R8 => vec![pixel.0[3]],
+                        Format::Rgba8 => pixel.0.to_vec(),
+                    })
+                    .collect();
+
+                assert_eq!(row, &expected, "row {y} of the {format:?} atlas");
+            }
+        }
+    }
+
+    #[test]
+    #[allow(
+        clippy::cast_possible_truncation,
+        clippy::cast_sign_loss,
+        clippy::cast_precision_loss
+    )]
+    fn sdf_of_a_straight_stem() {
+        let font = crate::text::test_font();
+        // the I is a plain rectangle
+        let glyph = font.glyph_id('I').expect("the font has an I");
+        let (font_size, spread, padding) = (64.0, 4.0, 4);
+        let bitmap = GlyphBitmap::sdf(&font, glyph, font_size, spread, padding);
+
+        let scale = font_size / font.units_per_em();
+        let bounds = font.glyph_bounds(glyph).expect("I has bounds");
+        let (min, max) = (bounds.min * scale, bounds.max * scale);
+        let padding = padding as f32;
+
+        assert_eq!(bitmap.offset, min.floor() - Vec2::new(padding, padding));
+        assert_eq!(
+            bitmap.width,
+            (max.x.ceil() - min.x.floor() + 2.0 * padding) as u32
+        );
+        assert_eq!(
+            bitmap.height,
+            (max.y.ceil() - min.y.floor() + 2.0 * padding) as u32
+        );
+        assert_eq!(bitmap.pixels.len(), (bitmap.width * bitmap.height) as usize);
+
+        // halfway up the stem only the left and right edges are near
+        let row = bitmap.height / 2;
+        let left = min.x - bitmap.offset.x;
+        let right = max.x - bitmap.offset.x;
+
+        for column in 0..bitmap.width {
+            let x = column as f32 + 0.5;
+            let distance = (left - x).max(x - right);
+            let expected = (0.5 - 0.5 * distance / spread).clamp(0.0, 1.0) * 255.0;
+            let value = bitmap.pixels[(row * bitmap.width + column) as usize];
+
+            assert!(
+                (f32::from(value) - expected).abs() <= 1.0,
+                "column {column} is {value}, not {expected}"
+            );
+        }
+
+        // the padding is far enough from the glyph to be empty
+        assert_eq!(bitmap.pixels[0], 0);
+    }
+
+    #[test]
+    fn sdf_of_space_is_empty() {
+        let font = crate::text::test_font();
+        let space = font.glyph_id(' ').expect("the font has a space");
+        let bitmap = GlyphBitmap::sdf(&font, space, 64.0, 4.0, 4);
+
+        assert_eq!((bitmap.width, bitmap.height), (0, 0));
+        assert!(bitmap.pixels.is_empty());
+    }
+
+    #[test]
+    fn builder_places_every_char() {
+        let font = crate::text::test_font();
+        let atlas = Builder::new(&font)
+            .add_chars("AB \u{10FFFD}".chars())
+            .add_width(256)
+            .build();
+
+        assert_eq!(atlas.encoding(), Encoding::DistanceField);
+        assert!(atlas
+            .font()
+            .is_some_and(|atlas_font| atlas_font.id() == font.id()));
+        assert!(atlas.image.height().is_power_of_two());
+
+        for c in ['A', 'B', ' '] {
+            let glyph = font.glyph_id(c).expect("the font has the char");
+            assert!(atlas.char_data(c).is_some(), "{c:?} is in the atlas");
+            assert!(atlas.glyph_data(glyph).is_some(), "{c:?} is in the atlas");
+        }
+
+        // not in the font
+        assert!(atlas.char_data('\u{10FFFD}').is_none());
+    }
+}
@@ -0,0 +1,77 @@
+use std::borrow::Cow;
+use std::ops::Range;
+use unicode_bidi::BidiInfo;
+
+/// Byte ranges of `text` that each go in one direction, in visual order from left
+/// to right. The flag is `true` for right to left runs. Lines are reordered
+/// separately so `text` should not contain line breaks that will be wrapped at.
+pub fn visual_runs(text: &str) -> Vec<(Range<usize>, bool)> {
+    let info = BidiInfo::new(text, None);
+
+    if !info.has_rtl() {
+        return vec![(0..text.len(), false)];
+    }
+
+    info.paragraphs
+        .iter()
+        .flat_map(|paragraph| {
+            let (levels, runs) = info.visual_runs(paragraph, paragraph.range.clone());
+
+            runs.into_iter().map(move |run| {
+                let rtl = levels[run.start].is_rtl();
+                (run, rtl)
+            })
+        })
+        .collect()
+}
+
+/// `text` with its characters in display order for placing them one by one
+/// from left to right. Mirrored characters like brackets are not swapped.
+pub fn reorder(text: &str) -> Cow<'_, str> {
+    let info = BidiInfo::new(text, None);
+
+    if !info.has_rtl() {
+        return Cow::Borrowed(text);
+    }
+
+    info.paragraphs
+        .iter()
+        .map(|paragraph| info.reorder_line(paragraph, paragraph.range.clone()))
+        .collect::<String>()
+        .into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // alef bet gimel, two bytes each
+    const HEBREW: &str = "\u{5d0}\u{5d1}\u{5d2}";
+
+    #[test]
+    fn left_to_right_text_is_one_run() {
+        assert_eq!(visual_runs("abc def"), [(0..7, false)]);
+        assert!(matches!(reorder("abc def"), Cow::Borrowed("abc def")));
+    }
+
+    #[test]
+    fn right_to_left_run_in_left_to_right_text() {
+        let text = format!("ab {HEBREW} cd");
+
+        assert_eq!(
+            visual_runs(&text),
+            [(0..3, false), (3..9, true), (9..12, false)]
+        );
+        assert_eq!(reorder(&text), "ab \u{5d2}\u{5d1}\u{5d0} cd");
+    }
+
+    #[test]
+    fn right_to_left_paragraph() {
+        // the first strong character makes the paragraph right to left,
+        // so the latin run goes on the left
+        let text = format!("{HEBREW} ab");
+
+        assert_eq!(visual_runs(&text), [(7..9, false), (0..7, true)]);
+        assert_eq!(reorder(&text), "ab \u{5d2}\u{5d1}\u{5d0}");
+    }
+}
@@ -0,0 +1,216 @@
+use super::atlas::{self, Format, GlyphBitmap, PlacedGlyph};
+use super::font::{Font, FontId, GlyphId};
+use super::packer::ShelfPacker;
+use super::CharData;
+use crate::math::vector::Vec2;
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct GlyphKey {
+    pub font: FontId,
+    pub glyph: GlyphId,
+    /// The bits of the size in pixels per em, floats are not `Hash`.
+    pub size: u32,
+}
+
+impl GlyphKey {
+    pub fn new(font: &Font, glyph: GlyphId, font_size: f32) -> Self {
+        Self {
+            font: font.id(),
+            glyph,
+            size: font_size.to_bits(),
+        }
+    }
+}
+
+struct Entry {
+    glyph: PlacedGlyph,
+    last_used: u64,
+}
+
+/// A font atlas texture that glyphs are rasterized into when they are first used.
+/// When it is full the least recently used glyphs are evicted to make room,
+/// except the ones used since the last `next_frame` since they may still be drawn.
+pub struct GlyphCache {
+    texture: wgpu::Texture,
+    packer: ShelfPacker,
+    entries: HashMap<GlyphKey, Entry>,
+    frame: u64,
+    spread: f32,
+    padding: u32,
+}
+
+impl GlyphCache {
+    pub fn new(device: &wgpu::Device, width: u32, height: u32, format: Format) -> Self {
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("glyph cache"),
+            size: wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: format.texture_format(),
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            view_formats: &[format.texture_format()],
+        });
+
+        Self {
+            texture,
+            packer: ShelfPacker::new(width, height),
+            entries: HashMap::new(),
+            frame: 0,
+            spread: 8.0,
+            padding: 8,
+        }
+    }
+
+    pub fn texture(&self) -> &wgpu::Texture {
+        &self.texture
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    pub fn size(&self) -> Vec2 {
+        Vec2::new(self.packer.width() as f32, self.packer.height() as f32)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Call once per frame after the previous frame's text was submitted.
+    pub fn next_frame(&mut self) {
+        self.frame += 1;
+    }
+
+    /// Forgets every glyph, the texture keeps its old contents until they are overwritten.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.packer.clear();
+    }
+
+    /// Looks up a glyph, rasterizing and uploading it if it is not cached yet.
+    /// `None` when the glyph does not fit even after evicting everything that can be.
+    pub fn get(
+        &mut self,
+        queue: &wgpu::Queue,
+        font: &Font,
+        glyph: GlyphId,
+        font_size: f32,
+    ) -> Option<PlacedGlyph> {
+        let key = GlyphKey::new(font, glyph, font_size);
+
+        if let Some(entry) = self.entries.get_mut(&key) {
+            entry.last_used = self.frame;
+            return Some(entry.glyph);
+        }
+
+        let bitmap = GlyphBitmap::sdf(font, glyph, font_size, self.spread, self.padding);
+
+        // glyphs like space have nothing to draw and take no space
+        let (x, y) = if bitmap.pixels.is_empty() {
+            (0, 0)
+        } else {
+            loop {
+                if let Some(position) = self.packer.allocate(bitmap.width, bitmap.height) {
+                    break position;
+                }
+
+                if !self.evict() {
+                    return None;
+                }
+            }
+        };
+
+        let placed = PlacedGlyph {
+            x,
+            y,
+            width: bitmap.width,
+            height: bitmap.height,
+            offset: bitmap.offset,
+        };
+
+        self.upload(queue, placed, &bitmap);
+
+        self.entries.insert(
+            key,
+            Entry {
+                glyph: placed,
+                last_used: self.frame,
+            },
+        );
+
+        Some(placed)
+    }
+
+    /// The atlas placement of `c` for `Line::push_with`. Characters missing from
+    /// the font use the font's missing glyph box so any text can be shown.
+    pub fn char_data(
+        &mut self,
+        queue: &wgpu::Queue,
+        font: &Font,
+        font_size: f32,
+        c: char,
+    ) -> Option<CharData> {
+        let glyph = font.glyph_id(c).unwrap_or(GlyphId(0));
+
+        self.glyph_data(queue, font, font_size, glyph)
+    }
+
+    /// Like `char_data` for a glyph that came out of the shaper.
+    pub fn glyph_data(
+        &mut self,
+        queue: &wgpu::Queue,
+        font: &Font,
+        font_size: f32,
+        glyph: GlyphId,
+    ) -> Option<CharData> {
+        let placed = self.get(queue, font, glyph, font_size)?;
+
+        let scale = font_size / font.units_per_em();
+        let (ascender, _, _) = font.vertical_metrics();
+
+        Some(CharData::from_pixels(
+            placed,
+            ascender * scale,
+            font.advance(glyph) * scale,
+            self.size(),
+        ))
+    }
+
+    /// Removes the least recently used glyph, `false` if every glyph is in use this frame.
+    fn evict(&mut self) -> bool {
+        let oldest = self
+            .entries
+            .iter()
+            .filter(|(_, entry)| entry.last_used < self.frame)
+            .min_by_key(|(_, entry)| entry.last_used)
+            .map(|(&key, _)| key);
+
+        let Some(entry) = oldest.and_then(|key| self.entries.remove(&key)) else {
+            return false;
+        };
+
+        if entry.glyph.width > 0 {
+            self.packer
+                .deallocate(entry.glyph.x, entry.glyph.y, entry.glyph.width);
+        }
+
+        true
+    }
+
+    /// Writes only the glyph's rectangle of the texture.
+    fn upload(&self, queue: &wgpu::Queue, placed: PlacedGlyph, bitmap: &GlyphBitmap) {
+        if bitmap.pixels.is_empty() {
+            return;
+        }
+
+        atlas::write_texture(queue, &self.texture, placed.x, placed.y, &bitmap.to_rgba());
+    }
+}
@@ -0,0 +1,133 @@
+use super::outline::{GlyphOutline, OutlineCollector};
+use crate::math::aabb::Aabb2;
+use crate::math::vector::Vec2;
+
+pub use ttf_parser::GlyphId;
+
+/// A TTF or OTF font loaded at runtime.
+/// The bytes are shared so cloning a font is cheap.
+#[derive(Clone)]
+pub struct Font {
+    data: std::sync::Arc<[u8]>,
+    index: u32,
+    id: FontId,
+}
+
+/// Identifies a loaded font, clones of a font share the same id.
+/// Every call to `Font::from_bytes` gets a new one, even for the same bytes,
+/// and ids are never reused after a font is dropped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FontId(u64);
+
+static NEXT_ID: std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(0);
+
+impl std::fmt::Debug for Font {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Font")
+            .field("len", &self.data.len())
+            .field("index", &self.index)
+            .field("id", &self.id)
+            .finish()
+    }
+}
+
+impl Font {
+    /// `index` selects the face in a font collection, use 0 for a single font file.
+    pub fn from_bytes(
+        data: impl Into<std::sync::Arc<[u8]>>,
+        index: u32,
+    ) -> Result<Self, ttf_parser::FaceParsingError> {
+        let data = data.into();
+
+        ttf_parser::Face::parse(&data, index)?;
+
+        let id = FontId(NEXT_ID.fetch_add(1, std::sync::atomic::Ordering::Relaxed));
+
+        Ok(Self { data, index, id })
+    }
+
+    /// Parsing only reads the table directory so it is cheap to do per call.
+    pub fn face(&self) -> ttf_parser::Face<'_> {
+        ttf_parser::Face::parse(&self.data, self.index).expect("font was validated when loaded")
+    }
+
+    pub fn id(&self) -> FontId {
+        self.id
+    }
+
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    pub fn index(&self) -> u32 {
+        self.index
+    }
+
+    pub fn units_per_em(&self) -> f32 {
+        f32::from(self.face().units_per_em())
+    }
+
+    /// Ascender, descender and line gap in font units, the descender is negative.
+    pub fn vertical_metrics(&self) -> (f32, f32, f32) {
+        let face = self.face();
+
+        (
+            f32::from(face.ascender()),
+            f32::from(face.descender()),
+            f32::from(face.line_gap()),
+        )
+    }
+
+    /// Position of the top of underlines and their thickness in font units,
+    /// the position is negative since it is below the baseline.
+    pub fn underline_metrics(&self) -> (f32, f32) {
+        let em = self.units_per_em();
+
+        self.face()
+            .underline_metrics()
+            .map_or((-0.1 * em, 0.05 * em), |metrics| {
+                (f32::from(metrics.position), f32::from(metrics.thickness))
+            })
+    }
+
+    /// Position of the top of strikethrough lines and their thickness in font units.
+    pub fn strikeout_metrics(&self) -> (f32, f32) {
+        let em = self.units_per_em();
+
+        self.face()
+            .strikeout_metrics()
+            .map_or((0.25 * em, 0.05 * em), |metrics| {
+                (f32::from(metrics.position), f32::from(metrics.thickness))
+            })
+    }
+
+    pub fn glyph_id(&self, c: char) -> Option<GlyphId> {
+        self.face().glyph_index(c)
+    }
+
+    /// Horizontal advance in font units.
+    pub fn advance(&self, glyph: GlyphId) -> f32 {
+        self.face().glyph_hor_advance(glyph).map_or(0.0, f32::from)
+    }
+
+    /// Bounds of the glyph in font units, `None` for glyphs without an outline like space.
+    pub fn glyph_bounds(&self, glyph: GlyphId) -> Option<Aabb2> {
+        self.face().glyph_bounding_box(glyph).map(|rect| {
+            Aabb2::new(
+                Vec2::new(f32::from(rect.x_min), f32::from(rect.y_min)),
+                Vec2::new(f32::from(rect.x_max), f32::from(rect.y_max)),
+            )
+        })
+    }
+
+    /// The outline of a glyph with curves flattened to lines within `tolerance`.
+    /// Points are in font units multiplied by `scale`, pass `1.0 / units_per_em`
+    /// to get em units. `None` for glyphs without an outline.
+    pub fn outline(&self, glyph: GlyphId, scale: f32, tolerance: f32) -> Option<GlyphOutline> {
+        let mut collector = OutlineCollector::new(scale, tolerance);
+
+        self.face().outline_glyph(glyph, &mut collector)?;
+
+        Some(collector.finish())
+    }
+}
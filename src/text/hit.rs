@@ -0,0 +1,246 @@
+use std::ops::Range;
+
+/// The horizontal extent of a glyph, all a caret needs to know about it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Extent {
+    /// Byte index in the text of the first character this glyph came from.
+    pub cluster: usize,
+    /// Left edge.
+    pub x: f32,
+    pub advance: f32,
+    pub rtl: bool,
+}
+
+impl Extent {
+    /// Where the caret goes when `fraction` of the glyph's characters come before it.
+    fn edge(self, fraction: f32) -> f32 {
+        if self.rtl {
+            self.x + self.advance * (1.0 - fraction)
+        } else {
+            self.x + self.advance * fraction
+        }
+    }
+
+    fn distance(self, x: f32) -> f32 {
+        (self.x - x).max(x - self.x - self.advance).max(0.0)
+    }
+}
+
+/// The glyphs of a line with the start of every cluster on it, kept sorted so
+/// finding the characters of a glyph is a binary search instead of a scan.
+#[derive(Debug, Clone, Default)]
+pub struct HitLine {
+    /// Byte range of the line without its line break.
+    pub range: Range<usize>,
+    glyphs: Vec<Extent>,
+    /// Every glyph's cluster once, in order.
+    clusters: Vec<usize>,
+}
+
+impl HitLine {
+    pub fn new(range: Range<usize>, glyphs: impl IntoIterator<Item = Extent>) -> Self {
+        let glyphs: Vec<_> = glyphs.into_iter().collect();
+        let mut clusters: Vec<_> = glyphs.iter().map(|glyph| glyph.cluster).collect();
+        clusters.sort_unstable();
+        clusters.dedup();
+
+        Self {
+            range,
+            glyphs,
+            clusters,
+        }
+    }
+
+    pub fn push(&mut self, glyph: Extent) {
+        let i = self
+            .clusters
+            .partition_point(|&cluster| cluster < glyph.cluster);
+
+        if self.clusters.get(i) != Some(&glyph.cluster) {
+            self.clusters.insert(i, glyph.cluster);
+        }
+
+        self.glyphs.push(glyph);
+    }
+
+    pub fn clear(&mut self) {
+        self.range = 0..0;
+        self.glyphs.clear();
+        self.clusters.clear();
+    }
+
+    /// The bytes a glyph covers, up to the next cluster of the line. Ligatures cover
+    /// several characters and marks cover the same ones as their base.
+    fn cluster_range(&self, glyph: Extent) -> Range<usize> {
+        let next = self
+            .clusters
+            .partition_point(|&cluster| cluster <= glyph.cluster);
+        let end = self.clusters.get(next).copied().unwrap_or(self.range.end);
+
+        glyph.cluster..end.max(glyph.cluster)
+    }
+}
+
+/// How far into `range` the byte at `index` is, counting characters so a caret
+/// inside a ligature splits it evenly.
+#[allow(clippy::cast_precision_loss)]
+fn fraction(text: &str, range: Range<usize>, index: usize) -> f32 {
+    let chars = text[range.clone()].char_indices();
+    let total = chars.clone().count().max(1);
+    // an index inside a character counts as past it
+    let before = chars.take_while(|(i, _)| range.start + i < index).count();
+
+    before as f32 / total as f32
+}
+
+/// The x of the caret in front of the byte at `index`, `empty_x` is used when
+/// the line has no glyphs.
+pub fn caret_x(text: &str, line: &HitLine, empty_x: f32, index: usize) -> f32 {
+    let containing = line
+        .glyphs
+        .iter()
+        .map(|&glyph| (glyph, line.cluster_range(glyph)))
+        .filter(|(_, range)| range.contains(&index))
+        .max_by(|(a, _), (b, _)| a.advance.total_cmp(&b.advance));
+
+    if let Some((glyph, range)) = containing {
+        return glyph.edge(fraction(text, range, index));
+    }
+
+    // past the end of the line the caret follows the last character
+    line.glyphs
+        .iter()
+        .max_by_key(|glyph| glyph.cluster)
+        .map_or(empty_x, |glyph| glyph.edge(1.0))
+}
+
+/// The byte index whose caret is closest to `x`.
+pub fn index_at(text: &str, line: &HitLine, x: f32) -> usize {
+    let Some(&glyph) = line
+        .glyphs
+        .iter()
+        .min_by(|a, b| a.distance(x).total_cmp(&b.distance(x)))
+    else {
+        return line.range.start;
+    };
+
+    let range = line.cluster_range(glyph);
+
+    text[range.clone()]
+        .char_indices()
+        .map(|(i, _)| range.start + i)
+        .chain([range.end])
+        .min_by(|&a, &b| {
+            let a = (glyph.edge(fraction(text, range.clone(), a)) - x).abs();
+            let b = (glyph.edge(fraction(text, range.clone(), b)) - x).abs();
+            a.total_cmp(&b)
+        })
+        .unwrap_or(range.start)
+}
+
+/// The horizontal spans covering the bytes in `selection`, adjacent ones merged.
+pub fn selection(text: &str, line: &HitLine, selection: Range<usize>) -> Vec<Range<f32>> {
+    let mut spans: Vec<Range<f32>> = line
+        .glyphs
+        .iter()
+        .filter_map(|&glyph| {
+            let range = line.cluster_range(glyph);
+            let start = selection.start.clamp(range.start, range.end);
+            let end = selection.end.clamp(range.start, range.end);
+
+            if start >= end {
+                return None;
+            }
+
+            let a = glyph.edge(fraction(text, range.clone(), start));
+            let b = glyph.edge(fraction(text, range, end));
+
+            Some(a.min(b)..a.max(b))
+        })
+        .collect();
+
+    spans.sort_by(|a, b| a.start.total_cmp(&b.start));
+
+    // glyphs that touch make one rectangle, small gaps come from float error
+    spans.dedup_by(|next, span| {
+        let touching = next.start <= span.end + 0.01;
+        if touching {
+            span.end = span.end.max(next.end);
+        }
+        touching
+    });
+
+    spans
+}
+
+// the positions are whole numbers so they are compared exactly
+#[cfg(test)]
+#[allow(clippy::float_cmp)]
+mod tests {
+    use super::*;
+
+    fn glyph(cluster: usize, x: f32, advance: f32, rtl: bool) -> Extent {
+        Extent {
+            cluster,
+            x,
+            advance,
+            rtl,
+        }
+    }
+
+    #[test]
+    fn ligature_is_split_evenly() {
+        // "fix" with "fi" as one glyph
+        let line = HitLine::new(
+            0..3,
+            [glyph(0, 0.0, 20.0, false), glyph(2, 20.0, 10.0, false)],
+        );
+
+        assert_eq!(caret_x("fix", &line, 0.0, 1), 10.0);
+        assert_eq!(caret_x("fix", &line, 0.0, 2), 20.0);
+        assert_eq!(caret_x("fix", &line, 0.0, 3), 30.0);
+        assert_eq!(index_at("fix", &line, 8.0), 1);
+        assert_eq!(selection("fix", &line, 1..3), vec![10.0..30.0]);
+    }
+
+    #[test]
+    fn right_to_left_clusters_in_visual_order() {
+        // two right to left characters of two bytes each, drawn right to left
+        let text = "אב";
+        let line = HitLine::new(
+            0..4,
+            [glyph(2, 0.0, 10.0, true), glyph(0, 10.0, 10.0, true)],
+        );
+
+        assert_eq!(caret_x(text, &line, 0.0, 0), 20.0);
+        assert_eq!(caret_x(text, &line, 0.0, 2), 10.0);
+        assert_eq!(index_at(text, &line, 1.0), 4);
+        assert_eq!(selection(text, &line, 0..2), vec![10.0..20.0]);
+    }
+
+    #[test]
+    fn pushed_glyphs_match_new() {
+        let glyphs = [
+            glyph(3, 0.0, 5.0, false),
+            glyph(0, 5.0, 5.0, false),
+            glyph(3, 10.0, 0.0, false),
+            glyph(1, 10.0, 5.0, false),
+        ];
+        let mut pushed = HitLine {
+            range: 0..5,
+            ..HitLine::default()
+        };
+
+        for glyph in glyphs {
+            pushed.push(glyph);
+        }
+
+        let built = HitLine::new(0..5, glyphs);
+
+        assert_eq!(pushed.clusters, vec![0, 1, 3]);
+        assert_eq!(pushed.clusters, built.clusters);
+        assert_eq!(built.cluster_range(glyphs[0]), 3..5);
+        assert_eq!(built.cluster_range(glyphs[1]), 0..1);
+        assert_eq!(built.cluster_range(glyphs[3]), 1..3);
+    }
+}
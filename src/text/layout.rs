@@ -0,0 +1,838 @@
+use super::cache::GlyphCache;
+use super::font::{Font, GlyphId};
+use super::hit::{self, Extent, HitLine};
+use super::rich::{RichText, Style};
+use super::shape::{shape_run, ShapedGlyph};
+use super::{shader_color, Quad, TexRect2D};
+use crate::math::aabb::Aabb2;
+use crate::math::vector::Vec2;
+use std::ops::Range;
+use unicode_bidi::{Level, ParagraphBidiInfo};
+use unicode_linebreak::BreakOpportunity;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Align {
+    #[default]
+    Left,
+    Center,
+    Right,
+    /// Stretches the spaces so every line but the last of each paragraph fills the width.
+    Justify,
+}
+
+/// How to lay out a paragraph, distances are in pixels.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TextLayout {
+    /// Lines wrap at this width, words longer than it overflow.
+    pub max_width: f32,
+    /// Distance from one baseline to the next.
+    pub line_height: f32,
+    pub align: Align,
+}
+
+impl TextLayout {
+    /// No wrapping, left aligned, with the font's own line spacing.
+    pub fn new(font: &Font, font_size: f32) -> Self {
+        let (ascender, descender, line_gap) = font.vertical_metrics();
+
+        Self {
+            max_width: f32::INFINITY,
+            line_height: (ascender - descender + line_gap) * font_size / font.units_per_em(),
+            align: Align::Left,
+        }
+    }
+}
+
+/// A glyph with its final position in the paragraph.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PositionedGlyph {
+    pub glyph: GlyphId,
+    /// Byte index in the text of the first character this glyph came from.
+    pub cluster: usize,
+    /// Where to draw the glyph in pixels, y up from the top of the paragraph.
+    pub position: Vec2,
+    pub advance: f32,
+    /// Index of the span in `Paragraph::spans` whose style the glyph has.
+    pub span: usize,
+    pub rtl: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LineInfo {
+    /// The glyphs of this line in `Paragraph::glyphs`.
+    pub glyphs: Range<usize>,
+    /// The bytes of the text on this line, including trailing spaces and the newline.
+    pub text: Range<usize>,
+    /// Left edge of the line in pixels.
+    pub x: f32,
+    /// Width without trailing spaces in pixels.
+    pub width: f32,
+    /// Height of the baseline in pixels, negative since it is below the top.
+    pub baseline: f32,
+}
+
+/// Text wrapped into lines at the unicode line break opportunities.
+#[derive(Debug, Clone)]
+pub struct Paragraph {
+    text: String,
+    spans: Vec<(Range<usize>, Style)>,
+    /// The largest ascender of all spans, the first baseline is this far below the top.
+    ascender: f32,
+    layout: TextLayout,
+    glyphs: Vec<PositionedGlyph>,
+    lines: Vec<LineInfo>,
+    /// The glyphs of every line for hit testing, made once the lines are aligned.
+    hit_lines: Vec<HitLine>,
+    size: Vec2,
+}
+
+/// The text between two mandatory breaks, shaped but not yet wrapped.
+struct HardLine<'a> {
+    /// In logical order with the span of each glyph, advances and offsets are in pixels.
+    shaped: Vec<(ShapedGlyph, usize)>,
+    bidi: ParagraphBidiInfo<'a>,
+    /// Byte index of the content in the whole text.
+    start: usize,
+}
+
+/// A piece of a line that can not be broken, usually a word and the spaces after it.
+struct Word {
+    glyphs: Range<usize>,
+    end: usize,
+    width: f32,
+    trailing: f32,
+}
+
+impl Paragraph {
+    pub fn new(font: &Font, font_size: f32, text: &str, layout: TextLayout) -> Self {
+        Self::rich(
+            &RichText::new().add_span(text, Style::new(font, font_size)),
+            layout,
+        )
+    }
+
+    pub fn rich(text: &RichText, layout: TextLayout) -> Self {
+        let spans = text.spans().to_vec();
+        let ascender = spans
+            .iter()
+            .map(|(_, style)| style.ascender())
+            .fold(0.0, f32::max);
+
+        let mut paragraph = Self {
+            text: text.as_str().to_owned(),
+            spans,
+            ascender,
+            layout,
+            glyphs: Vec::new(),
+            lines: Vec::new(),
+            hit_lines: Vec::new(),
+            size: Vec2::new(0.0, 0.0),
+        };
+
+        let text = text.as_str();
+        let mut start = 0;
+
+        for (end, _) in unicode_linebreak::linebreaks(text)
+            .filter(|&(_, opportunity)| opportunity == BreakOpportunity::Mandatory)
+        {
+            paragraph.push_hard_line(text, start..end);
+            start = end;
+        }
+
+        if paragraph.lines.is_empty() {
+            paragraph.push_hard_line(text, 0..0);
+        }
+
+        #[allow(clippy::cast_precision_loss)]
+        let height = paragraph.lines.len() as f32 * layout.line_height;
+
+        let width = paragraph
+            .lines
+            .iter()
+            .map(|line| line.width)
+            .fold(0.0, f32::max);
+
+        // align inside the wrap width, or the widest line when nothing wraps
+        let area = if layout.max_width.is_finite() {
+            layout.max_width
+        } else {
+            width
+        };
+
+        for line in &mut paragraph.lines {
+            let free = (area - line.width).max(0.0);
+
+            line.x = match layout.align {
+                Align::Left | Align::Justify => 0.0,
+                Align::Center => free / 2.0,
+                Align::Right => free,
+            };
+
+            for glyph in &mut paragraph.glyphs[line.glyphs.clone()] {
+                glyph.position.x += line.x;
+            }
+        }
+
+        paragraph.hit_lines = (0..paragraph.lines.len())
+            .map(|line| paragraph.hit_line(line))
+            .collect();
+
+        paragraph.size = Vec2::new(width, height);
+
+        paragraph
+    }
+
+    /// The span that the byte at `index` is in.
+    fn span_at(&self, index: usize) -> usize {
+        self.spans
+            .partition_point(|(range, _)| range.end <= index)
+            .min(self.spans.len().saturating_sub(1))
+    }
+
+    /// Lays out the text between two mandatory breaks, which may wrap into several lines.
+    fn push_hard_line(&mut self, text: &str, range: Range<usize>) {
+        let content = text[range.clone()].trim_end_matches(is_newline);
+        let bidi = ParagraphBidiInfo::new(content, None);
+
+        // shape each direction and span on its own and keep the glyphs in logical
+        // order, they are put in visual order once the lines are known
+        let mut shaped = Vec::new();
+        let mut run_start = 0;
+
+        for i in 1..=content.len() {
+            let span = self.span_at(range.start + run_start);
+
+            if i < content.len()
+                && bidi.levels[i] == bidi.levels[run_start]
+                && self.span_at(range.start + i) == span
+            {
+                continue;
+            }
+
+            let style = &self.spans[span].1;
+            let rtl = bidi.levels[run_start].is_rtl();
+            let mut run = shape_run(&style.font, content, run_start..i, rtl);
+
+            if rtl {
+                run.reverse();
+            }
+
+            // clusters index the whole text from here on
+            let offset = u32::try_from(range.start).expect("text is shorter than 4GiB");
+
+            shaped.extend(run.into_iter().map(|glyph| {
+                let glyph = ShapedGlyph {
+                    cluster: glyph.cluster + offset,
+                    advance: glyph.advance * style.scale(),
+                    offset: glyph.offset * style.scale(),
+                    ..glyph
+                };
+
+                (glyph, span)
+            }));
+
+            run_start = i;
+        }
+
+        let opportunities: Vec<usize> = unicode_linebreak::linebreaks(content)
+            .map(|(index, _)| range.start + index)
+            .collect();
+
+        let mut words = Vec::new();
+        let mut glyph = 0;
+
+        for (i, &end) in opportunities.iter().enumerate() {
+            let first = glyph;
+            let mut width = 0.0;
+            let mut trailing = 0.0;
+
+            while glyph < shaped.len() && (shaped[glyph].0.cluster as usize) < end {
+                let advance = shaped[glyph].0.advance.x;
+                let space =
+                    text[shaped[glyph].0.cluster as usize..].starts_with(char::is_whitespace);
+
+                width += advance;
+                trailing = if space { trailing + advance } else { 0.0 };
+                glyph += 1;
+            }
+
+            // the last piece takes the newline so the lines cover all of the text
+            let end = if i + 1 == opportunities.len() {
+                range.end
+            } else {
+                end
+            };
+
+            words.push(Word {
+                glyphs: first..glyph,
+                end,
+                width,
+                trailing,
+            });
+        }
+
+        let hard_line = HardLine {
+            shaped,
+            bidi,
+            start: range.start,
+        };
+
+        let mut line_words: Vec<Word> = Vec::new();
+        let mut line_width = 0.0;
+        let mut line_start = range.start;
+
+        for word in words {
+            let fits = line_width + word.width - word.trailing <= self.layout.max_width;
+
+            if !fits && !line_words.is_empty() {
+                let end = line_words.last().map_or(line_start, |word| word.end);
+                self.push_line(&hard_line, &line_words, line_start..end, false);
+                line_start = end;
+                line_words.clear();
+                line_width = 0.0;
+            }
+
+            line_width += word.width;
+            line_words.push(word);
+        }
+
+        self.push_line(&hard_line, &line_words, line_start..range.end, true);
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    fn push_line(&mut self, hard_line: &HardLine, words: &[Word], text: Range<usize>, last: bool) {
+        let layout = self.layout;
+
+        let trailing = words.last().map_or(0.0, |word| word.trailing);
+        let width = words.iter().map(|word| word.width).sum::<f32>() - trailing;
+
+        let baseline = -self.ascender - self.lines.len() as f32 * layout.line_height;
+        let free = (layout.max_width - width).max(0.0);
+
+        // justify by stretching the gaps between words, not the trailing spaces
+        let gaps = words.len().saturating_sub(1);
+        let justify = layout.align == Align::Justify && !last && gaps > 0 && free.is_finite();
+        let stretch = if justify { free / gaps as f32 } else { 0.0 };
+        let gap_ends: Vec<usize> = words[..gaps]
+            .iter()
+            .filter(|word| !word.glyphs.is_empty())
+            .map(|word| word.glyphs.end - 1)
+            .collect();
+
+        let glyphs = words.first().map_or(0, |word| word.glyphs.start)
+            ..words.last().map_or(0, |word| word.glyphs.end);
+
+        // levels of the line's bytes with trailing spaces moved to the paragraph
+        // direction, then the glyphs are reversed run by run into visual order
+        let content = hard_line.bidi.text.len();
+        let line = (text.start - hard_line.start)..(text.end - hard_line.start).min(content);
+        let levels = if line.is_empty() {
+            Vec::new()
+        } else {
+            hard_line.bidi.reordered_levels(line)
+        };
+        let glyph_levels: Vec<Level> = hard_line.shaped[glyphs.clone()]
+            .iter()
+            .map(|(glyph, _)| levels[glyph.cluster as usize - hard_line.start])
+            .collect();
+
+        // trailing spaces of a right to left paragraph end up on the left, let them hang
+        let first = self.glyphs.len();
+        let mut x = if hard_line.bidi.paragraph_level.is_rtl() {
+            -trailing
+        } else {
+            0.0
+        };
+
+        for i in ParagraphBidiInfo::reorder_visual(&glyph_levels) {
+            let index = glyphs.start + i;
+            let (shaped, span) = hard_line.shaped[index];
+            let advance = shaped.advance.x;
+            let rtl = glyph_levels[i].is_rtl();
+            let gap = gap_ends.contains(&index);
+
+            // the space after a right to left word is on its left
+            if gap && rtl {
+                x += stretch;
+            }
+
+            self.glyphs.push(PositionedGlyph {
+                glyph: shaped.glyph,
+                cluster: shaped.cluster as usize,
+                position: Vec2::new(x, baseline) + shaped.offset,
+                advance,
+                span,
+                rtl,
+            });
+
+            x += advance;
+
+            if gap && !rtl {
+                x += stretch;
+            }
+        }
+
+        self.lines.push(LineInfo {
+            glyphs: first..self.glyphs.len(),
+            text,
+            x: 0.0,
+            width: if justify { width + free } else { width },
+            baseline,
+        });
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// The byte range of every span in the text with its style.
+    pub fn spans(&self) -> &[(Range<usize>, Style)] {
+        &self.spans
+    }
+
+    pub fn layout(&self) -> TextLayout {
+        self.layout
+    }
+
+    pub fn glyphs(&self) -> &[PositionedGlyph] {
+        &self.glyphs
+    }
+
+    pub fn lines(&self) -> &[LineInfo] {
+        &self.lines
+    }
+
+    /// Width of the longest line and height of all lines in pixels.
+    pub fn size(&self) -> Vec2 {
+        self.size
+    }
+
+    /// The area the lines take up in pixels, y up from the top of the paragraph.
+    pub fn bounds(&self) -> Aabb2 {
+        let left = self
+            .lines
+            .iter()
+            .map(|line| line.x)
+            .fold(f32::INFINITY, f32::min);
+        let right = self
+            .lines
+            .iter()
+            .map(|line| line.x + line.width)
+            .fold(f32::NEG_INFINITY, f32::max);
+
+        Aabb2::new(Vec2::new(left, -self.size.y), Vec2::new(right, 0.0))
+    }
+
+    /// The top and bottom of a line in pixels.
+    #[allow(clippy::cast_precision_loss)]
+    fn line_span(&self, line: usize) -> (f32, f32) {
+        let top = -(line as f32) * self.layout.line_height;
+
+        (top, top - self.layout.line_height)
+    }
+
+    /// The box of a glyph from `glyphs` that a click selects, its advance by the line height.
+    pub fn glyph_rect(&self, glyph: usize) -> Aabb2 {
+        let line = self
+            .lines
+            .iter()
+            .position(|line| line.glyphs.contains(&glyph))
+            .unwrap_or(0);
+        let (top, bottom) = self.line_span(line);
+        let glyph = self.glyphs[glyph];
+        let x = glyph.position.x;
+
+        Aabb2::new(Vec2::new(x, bottom), Vec2::new(x + glyph.advance, top))
+    }
+
+    /// The line the byte at `index` is on, the end of the text is on the last line.
+    pub fn line_of(&self, index: usize) -> usize {
+        self.lines
+            .iter()
+            .position(|line| line.text.contains(&index))
+            .unwrap_or(self.lines.len().saturating_sub(1))
+    }
+
+    /// The line without its line break and the extents of its glyphs for `hit`.
+    fn hit_line(&self, line: usize) -> HitLine {
+        let info = &self.lines[line];
+        let content = self.text[info.text.clone()].trim_end_matches(is_newline);
+        let extents = self.glyphs[info.glyphs.clone()].iter().map(|glyph| Extent {
+            cluster: glyph.cluster,
+            x: glyph.position.x,
+            advance: glyph.advance,
+            rtl: glyph.rtl,
+        });
+
+        HitLine::new(info.text.start..info.text.start + content.len(), extents)
+    }
+
+    /// The caret in front of the byte at `index` as its top and bottom point in pixels.
+    pub fn caret(&self, index: usize) -> (Vec2, Vec2) {
+        let line = self.line_of(index);
+        let x = hit::caret_x(&self.text, &self.hit_lines[line], self.lines[line].x, index);
+        let (top, bottom) = self.line_span(line);
+
+        (Vec2::new(x, top), Vec2::new(x, bottom))
+    }
+
+    /// The byte index of the caret closest to `point`, which is in pixels y up
+    /// from the top of the paragraph. Points above or below the text go to the
+    /// first or last line.
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    pub fn index_at(&self, point: Vec2) -> usize {
+        let line = (-point.y / self.layout.line_height).floor().max(0.0) as usize;
+        let line = line.min(self.lines.len() - 1);
+        let index = hit::index_at(&self.text, &self.hit_lines[line], point.x);
+
+        // the end of a wrapped line is the start of the next, stay in front of its last character
+        if index == self.lines[line].text.end && line + 1 < self.lines.len() {
+            return self.text[..index]
+                .char_indices()
+                .next_back()
+                .map_or(index, |(i, _)| i);
+        }
+
+        index
+    }
+
+    /// Rectangles covering the bytes in `selection`, one or more per line it is on.
+    pub fn selection_rects(&self, selection: Range<usize>) -> Vec<Aabb2> {
+        (0..self.lines.len())
+            .filter(|&line| {
+                let text = &self.lines[line].text;
+                text.start < selection.end && selection.start < text.end
+            })
+            .flat_map(|line| {
+                let (top, bottom) = self.line_span(line);
+
+                hit::selection(&self.text, &self.hit_lines[line], selection.clone())
+                    .into_iter()
+                    .map(move |span| {
+                        Aabb2::new(Vec2::new(span.start, bottom), Vec2::new(span.end, top))
+                    })
+            })
+            .collect()
+    }
+
+    /// The quads `uv_tris.wgsl` draws, in the same units as `Line`.
+    /// Underlines and strikethroughs come after the glyphs.
+    pub fn quads(&self, cache: &mut GlyphCache, queue: &wgpu::Queue) -> Vec<Quad> {
+        let width = cache.size().x;
+
+        let mut quads: Vec<Quad> = self
+            .glyphs
+            .iter()
+            .filter_map(|glyph| {
+                let style = &self.spans[glyph.span].1;
+                let data = cache.glyph_data(queue, &style.font, style.font_size, glyph.glyph)?;
+
+                // the atlas rects hang below the ascender line of their own font
+                let pen = glyph.position + Vec2::new(0.0, style.ascender());
+
+                Some(Quad {
+                    rect: data.tex_rect + pen / width,
+                    color: shader_color(style.color, style.alpha),
+                })
+            })
+            .collect();
+
+        for line in &self.lines {
+            for run in self.glyphs[line.glyphs.clone()].chunk_by(|a, b| a.span == b.span) {
+                let style = &self.spans[run[0].span].1;
+                let left = run[0].position.x;
+                let right = run[run.len() - 1].position.x + run[run.len() - 1].advance;
+
+                let decorations = [
+                    (style.underline, style.font.underline_metrics()),
+                    (style.strikethrough, style.font.strikeout_metrics()),
+                ];
+
+                // the metrics give the top of the line, like the post and OS/2 tables
+                for (_, (position, thickness)) in decorations.into_iter().filter(|(on, _)| *on) {
+                    let top = line.baseline + position * style.scale();
+                    let bottom = top - thickness * style.scale();
+
+                    quads.push(Quad {
+                        rect: TexRect2D::solid(
+                            Vec2::new(left, bottom) / width,
+                            Vec2::new(right, top) / width,
+                        ),
+                        color: shader_color(style.color, style.alpha),
+                    });
+                }
+            }
+        }
+
+        quads
+    }
+}
+
+fn is_newline(c: char) -> bool {
+    matches!(
+        c,
+        '\n' | '\r' | '\u{0B}' | '\u{0C}' | '\u{85}' | '\u{2028}' | '\u{2029}'
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::Srgb;
+    use crate::text::test_font;
+
+    const FONT_SIZE: f32 = 32.0;
+
+    fn assert_close(a: f32, b: f32) {
+        assert!((a - b).abs() < 1e-3, "{a} is not {b}");
+    }
+
+    /// The width of `text` on one line.
+    fn width(font: &Font, text: &str) -> f32 {
+        Paragraph::new(font, FONT_SIZE, text, TextLayout::new(font, FONT_SIZE))
+            .size()
+            .x
+    }
+
+    fn paragraph(font: &Font, text: &str, max_width: f32, align: Align) -> Paragraph {
+        let layout = TextLayout {
+            max_width,
+            align,
+            ..TextLayout::new(font, FONT_SIZE)
+        };
+
+        Paragraph::new(font, FONT_SIZE, text, layout)
+    }
+
+    fn line_texts(paragraph: &Paragraph) -> Vec<&str> {
+        paragraph
+            .lines()
+            .iter()
+            .map(|line| &paragraph.text()[line.text.clone()])
+            .collect()
+    }
+
+    #[test]
+    fn wraps_at_the_width_limit() {
+        let font = test_font();
+        let max_width = width(&font, "one two") + 0.5;
+        let paragraph = paragraph(&font, "one two one two", max_width, Align::Left);
+
+        // trailing spaces stay on the line they follow and do not count towards its width
+        assert_eq!(line_texts(&paragraph), ["one two ", "one two"]);
+        assert_close(paragraph.lines()[0].width, width(&font, "one two"));
+        assert!(paragraph.lines().iter().all(|line| line.width <= max_width));
+
+        let line_height = paragraph.layout().line_height;
+        assert_close(
+            paragraph.lines()[0].baseline - paragraph.lines()[1].baseline,
+            line_height,
+        );
+        assert_close(paragraph.size().y, 2.0 * line_height);
+    }
+
+    #[test]
+    fn long_words_overflow_on_their_own_line() {
+        let font = test_font();
+        let max_width = width(&font, "a b");
+        let paragraph = paragraph(&font, "a incomprehensibilities b", max_width, Align::Left);
+
+        assert_eq!(
+            line_texts(&paragraph),
+            ["a ", "incomprehensibilities ", "b"]
+        );
+        assert!(paragraph.lines()[1].width > max_width);
+    }
+
+    #[test]
+    fn newlines_always_break() {
+        let font = test_font();
+        let paragraph = paragraph(&font, "a\nb\n", f32::INFINITY, Align::Left);
+
+        assert_eq!(line_texts(&paragraph), ["a\n", "b\n"]);
+    }
+
+    #[test]
+    fn justify_fills_the_line_width() {
+        let font = test_font();
+        let max_width = width(&font, "aa bb cc") + 10.0;
+        let paragraph = paragraph(&font, "aa bb cc dd ee", max_width, Align::Justify);
+        let lines = paragraph.lines();
+
+        assert_eq!(line_texts(&paragraph), ["aa bb cc ", "dd ee"]);
+        assert_close(lines[0].width, max_width);
+
+        // the last glyph before the trailing space ends at the right edge
+        let glyphs = &paragraph.glyphs()[lines[0].glyphs.clone()];
+        let last = glyphs[glyphs.len() - 2];
+        assert_close(last.position.x + last.advance, max_width);
+
+        // the 10 pixels are shared by the two gaps
+        let b = |paragraph: &Paragraph| {
+            paragraph
+                .glyphs()
+                .iter()
+                .find(|glyph| glyph.cluster == 3)
+                .expect("the text has a b")
+                .position
+                .x
+        };
+        let left = self::paragraph(&font, "aa bb cc dd ee", max_width, Align::Left);
+        assert_close(b(&paragraph), b(&left) + 5.0);
+
+        // the last line keeps its natural width
+        assert_close(lines[1].width, width(&font, "dd ee"));
+    }
+
+    #[test]
+    fn alignment() {
+        let font = test_font();
+        let max_width = 400.0;
+        let natural = width(&font, "centred");
+
+        let center = paragraph(&font, "centred", max_width, Align::Center);
+        let right = paragraph(&font, "centred", max_width, Align::Right);
+
+        assert_close(center.lines()[0].x, (max_width - natural) / 2.0);
+        assert_close(right.lines()[0].x, max_width - natural);
+        assert_close(right.glyphs()[0].position.x, max_width - natural);
+    }
+
+    #[test]
+    fn decorations_lie_on_their_metrics() {
+        let Ok((_instance, device, queue)) = crate::util::get_gpu() else {
+            eprintln!("no gpu adapter, skipping");
+            return;
+        };
+
+        let font = test_font();
+        let plain = Style {
+            color: Srgb::new(1.0, 0.0, 0.0),
+            ..Style::new(&font, FONT_SIZE)
+        };
+        let decorated = Style {
+            color: Srgb::new(0.0, 0.0, 1.0),
+            underline: true,
+            strikethrough: true,
+            ..Style::new(&font, FONT_SIZE)
+        };
+        let text = RichText::new()
+            .add_span("plain ", plain.clone())
+            .add_span("lined", decorated.clone());
+        let paragraph = Paragraph::rich(&text, TextLayout::new(&font, FONT_SIZE));
+
+        let mut cache = GlyphCache::new(&device, 512, 512, super::super::atlas::Format::R8);
+        let quads = paragraph.quads(&mut cache, &queue);
+        let width = cache.size().x;
+
+        let (glyphs, solid): (Vec<&Quad>, Vec<&Quad>) =
+            quads.iter().partition(|quad| quad.rect.start_uv.x >= 0.0);
+
+        // each span keeps its own colour
+        assert_eq!(glyphs[0].color, shader_color(plain.color, 1.0));
+        assert_eq!(
+            glyphs[glyphs.len() - 1].color,
+            shader_color(decorated.color, 1.0)
+        );
+
+        let baseline = paragraph.lines()[0].baseline;
+        let first = paragraph.glyphs()[6];
+        let last = paragraph.glyphs()[paragraph.glyphs().len() - 1];
+
+        assert_eq!(solid.len(), 2, "an underline and a strikethrough");
+        // y is up, the underline is below the baseline and the strikethrough through the x height
+        assert!(solid[0].rect.end.y * width < baseline);
+        assert!(solid[1].rect.start.y * width > baseline);
+        assert!(solid[1].rect.end.y * width < baseline + decorated.ascender() / 2.0);
+
+        for (quad, (position, thickness)) in solid
+            .iter()
+            .zip([font.underline_metrics(), font.strikeout_metrics()])
+        {
+            let top = baseline + position * decorated.scale();
+
+            assert_close(quad.rect.end.y * width, top);
+            assert_close(
+                quad.rect.start.y * width,
+                top - thickness * decorated.scale(),
+            );
+            assert_close(quad.rect.start.x * width, first.position.x);
+            assert_close(quad.rect.end.x * width, last.position.x + last.advance);
+            assert_eq!(quad.color, shader_color(decorated.color, 1.0));
+        }
+    }
+
+    /// "ab " wrapped onto its own line, then "cd" and "ef" split by a newline.
+    fn three_lines(font: &Font) -> Paragraph {
+        let max_width = width(font, "ab") + 1.0;
+        let paragraph = paragraph(font, "ab cd\nef", max_width, Align::Left);
+
+        assert_eq!(line_texts(&paragraph), ["ab ", "cd\n", "ef"]);
+
+        paragraph
+    }
+
+    #[test]
+    fn carets_round_trip_on_every_line() {
+        let font = test_font();
+        let paragraph = three_lines(&font);
+
+        for index in 0..=paragraph.text().len() {
+            let (top, bottom) = paragraph.caret(index);
+            let line = paragraph.line_of(index);
+
+            assert_close(top.y, paragraph.line_span(line).0);
+            assert_eq!(paragraph.index_at((top + bottom) * 0.5), index);
+        }
+
+        // lines start at the left edge
+        assert_close(paragraph.caret(3).0.x, 0.0);
+        assert_close(paragraph.caret(6).0.x, 0.0);
+    }
+
+    #[test]
+    fn clicks_outside_the_text() {
+        let font = test_font();
+        let paragraph = three_lines(&font);
+        let line_height = paragraph.layout().line_height;
+
+        // past the end of a wrapped line the caret stays on it, in front of the space
+        let past_wrap = paragraph.index_at(Vec2::new(1000.0, -0.5 * line_height));
+        assert_eq!(past_wrap, 2);
+        assert_close(paragraph.caret(past_wrap).0.y, 0.0);
+
+        // past the end of a line that ends in a newline the caret goes in front of it
+        assert_eq!(paragraph.index_at(Vec2::new(1000.0, -1.5 * line_height)), 5);
+
+        // below the last line is the last line, above the first is the first
+        assert_eq!(
+            paragraph.index_at(Vec2::new(1000.0, -10.0 * line_height)),
+            8
+        );
+        assert_eq!(
+            paragraph.index_at(Vec2::new(-1000.0, -10.0 * line_height)),
+            6
+        );
+        assert_eq!(paragraph.index_at(Vec2::new(-1000.0, line_height)), 0);
+    }
+
+    #[test]
+    fn selection_spans_lines() {
+        let font = test_font();
+        let paragraph = three_lines(&font);
+        let rects = paragraph.selection_rects(1..7);
+
+        assert_eq!(rects.len(), 3);
+
+        for (line, rect) in rects.iter().enumerate() {
+            let (top, bottom) = paragraph.line_span(line);
+            assert_close(rect.max.y, top);
+            assert_close(rect.min.y, bottom);
+        }
+
+        // from the b to the end of the first line, all of cd, and the e
+        assert_close(rects[0].min.x, paragraph.caret(1).0.x);
+        assert_close(rects[1].max.x, paragraph.caret(5).0.x);
+        assert_close(rects[2].max.x, paragraph.caret(7).0.x);
+    }
+}
@@ -0,0 +1,607 @@
+pub mod atlas;
+pub mod bidi;
+pub mod cache;
+pub mod font;
+pub mod hit;
+pub mod layout;
+pub mod outline;
+pub mod packer;
+pub mod renderer;
+pub mod rich;
+pub mod rtexpacker;
+pub mod shape;
+pub mod vector;
+
+use crate::color::{LinearRgb, Srgb};
+use crate::math::aabb::Aabb2;
+use crate::math::vector::{Vec2, Vec4};
+use crate::util::AsBytes;
+use atlas::PlacedGlyph;
+use serde::{Deserialize, Serialize};
+use std::ops::{Add, Range};
+
+/// The pre-baked atlas, a coverage bitmap exported by rTexPacker.
+/// Use `atlas::Builder` to make a distance field atlas that stays sharp when scaled up.
+///
+/// # Panics
+/// On first use if the image and json built into the binary do not load,
+/// which the tests check.
+pub static ATLAS: std::sync::LazyLock<Atlas> = std::sync::LazyLock::new(|| {
+    Atlas::from_bytes(
+        include_bytes!("../atlas.png"),
+        include_str!("../atlas.json"),
+    )
+    .expect("the built in atlas is valid")
+});
+
+/// The font in `tests/fixtures/DejaVuSans.ttf`, for tests that need a real font.
+#[cfg(test)]
+pub fn test_font() -> font::Font {
+    font::Font::from_bytes(
+        include_bytes!("../../tests/fixtures/DejaVuSans.ttf").as_slice(),
+        0,
+    )
+    .expect("the fixture font parses")
+}
+
+/// Why text or an atlas could not be loaded.
+#[derive(Debug)]
+pub enum TextError {
+    Image(image::ImageError),
+    Json(rtexpacker::JsonError),
+    Font(ttf_parser::FaceParsingError),
+    /// The atlas has no glyph for a character of the text.
+    MissingChar(char),
+}
+
+impl std::fmt::Display for TextError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TextError::Image(_) => write!(f, "the atlas image could not be decoded"),
+            TextError::Json(_) => write!(f, "the atlas json is invalid"),
+            TextError::Font(_) => write!(f, "the font could not be parsed"),
+            TextError::MissingChar(c) => write!(f, "the atlas has no glyph for {c:?}"),
+        }
+    }
+}
+
+impl std::error::Error for TextError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            TextError::Image(error) => Some(error),
+            TextError::Json(error) => Some(error),
+            TextError::Font(error) => Some(error),
+            TextError::MissingChar(_) => None,
+        }
+    }
+}
+
+impl From<image::ImageError> for TextError {
+    fn from(error: image::ImageError) -> Self {
+        TextError::Image(error)
+    }
+}
+
+impl From<rtexpacker::JsonError> for TextError {
+    fn from(error: rtexpacker::JsonError) -> Self {
+        TextError::Json(error)
+    }
+}
+
+impl From<ttf_parser::FaceParsingError> for TextError {
+    fn from(error: ttf_parser::FaceParsingError) -> Self {
+        TextError::Font(error)
+    }
+}
+
+/// A font atlas image with the placement of every character in it.
+/// The glyphs are in the alpha channel, `encoding` says how.
+pub struct Atlas {
+    image: image::RgbaImage,
+    encoding: atlas::Encoding,
+    data: AtlasData,
+    /// The font the atlas was built from, to shape text with.
+    /// `None` for atlases that only map characters, like rTexPacker's.
+    font: Option<font::Font>,
+    /// The placement of every glyph by id, for text that went through the shaper.
+    glyphs: std::collections::HashMap<font::GlyphId, CharData>,
+}
+
+impl Atlas {
+    /// An atlas from an encoded image, like png, and the json rTexPacker exported with it.
+    pub fn from_bytes(image: &[u8], json: &str) -> Result<Self, TextError> {
+        let image = image::load_from_memory(image)?.to_rgba8();
+
+        Ok(Self::from_rtexpacker(image, json)?)
+    }
+
+    /// An atlas without any characters.
+    pub fn empty() -> Self {
+        Self {
+            image: image::RgbaImage::new(1, 1),
+            encoding: atlas::Encoding::Coverage,
+            data: AtlasData {
+                size: Vec2::new(1.0, 1.0),
+                font_size: 0.0,
+                char_data: std::collections::HashMap::new(),
+            },
+            font: None,
+            glyphs: std::collections::HashMap::new(),
+        }
+    }
+
+    /// An atlas exported by rTexPacker, `json` is the sprite sheet it writes next to the image.
+    /// rTexPacker packs rendered glyphs, so the alpha is coverage.
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    pub fn from_rtexpacker(
+        image: image::RgbaImage,
+        json: &str,
+    ) -> Result<Self, rtexpacker::JsonError> {
+        let data = rtexpacker::parse(json)?;
+        let size = (data.size.x as u32, data.size.y as u32);
+
+        if size != image.dimensions() {
+            return Err(rtexpacker::JsonError::ImageSize {
+                json: size,
+                image: image.dimensions(),
+            });
+        }
+
+        Ok(Self {
+            image,
+            encoding: atlas::Encoding::Coverage,
+            data,
+            font: None,
+            glyphs: std::collections::HashMap::new(),
+        })
+    }
+
+    pub fn image(&self) -> &image::RgbaImage {
+        &self.image
+    }
+
+    pub fn encoding(&self) -> atlas::Encoding {
+        self.encoding
+    }
+
+    pub fn char_data(&self, c: char) -> Option<CharData> {
+        self.data.char_data.get(&c.to_string()).copied()
+    }
+
+    /// Like `char_data` for a glyph that came out of the shaper.
+    pub fn glyph_data(&self, glyph: font::GlyphId) -> Option<CharData> {
+        self.glyphs.get(&glyph).copied()
+    }
+
+    /// The font text in this atlas is shaped with, if it was built from one.
+    pub fn font(&self) -> Option<&font::Font> {
+        self.font.as_ref()
+    }
+
+    /// Uploads the atlas to a new texture of its size, which does not have to be square.
+    pub fn create_texture(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        format: atlas::Format,
+    ) -> wgpu::Texture {
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("font atlas"),
+            size: wgpu::Extent3d {
+                width: self.image.width(),
+                height: self.image.height(),
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: format.texture_format(),
+            // copy source so it can be read back to check or save it
+            usage: wgpu::TextureUsages::TEXTURE_BINDING
+                | wgpu::TextureUsages::COPY_DST
+                | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[format.texture_format()],
+        });
+
+        atlas::write_texture(queue, &texture, 0, 0, &self.image);
+
+        texture
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+struct AtlasData<T = f32> {
+    size: Vec2<T>,
+    font_size: T,
+    char_data: std::collections::HashMap<String, CharData>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Copy, Clone)]
+pub struct CharData<T = f32> {
+    tex_rect: TexRect2D<T>,
+    advance: T,
+}
+
+/// Where a quad is drawn and which part of the atlas it shows.
+#[repr(C)]
+#[derive(Debug, Copy, Clone, Serialize, Deserialize)]
+pub struct TexRect2D<T = f32> {
+    start: Vec2<T>,
+    end: Vec2<T>,
+    start_uv: Vec2<T>,
+    end_uv: Vec2<T>,
+}
+
+impl CharData {
+    /// Positions end up in pixels over the atlas width like rTexPacker writes them,
+    /// with the origin on the ascender line so a line of text hangs below it.
+    #[allow(clippy::cast_precision_loss)]
+    fn from_pixels(glyph: PlacedGlyph, ascender: f32, advance: f32, size: Vec2) -> Self {
+        let start = glyph.offset - Vec2::new(0.0, ascender);
+        let end = start + Vec2::new(glyph.width as f32, glyph.height as f32);
+
+        // uv y goes down so the bottom of the glyph is the bottom of its rect
+        let start_uv = Vec2::new(glyph.x as f32, (glyph.y + glyph.height) as f32) / size;
+        let end_uv = Vec2::new((glyph.x + glyph.width) as f32, glyph.y as f32) / size;
+
+        CharData {
+            tex_rect: TexRect2D {
+                start: start / size.x,
+                end: end / size.x,
+                start_uv,
+                end_uv,
+            },
+            advance: advance / size.x,
+        }
+    }
+}
+
+/// A `Quad` in `uv_tris.wgsl`.
+#[repr(C)]
+#[derive(Debug, Copy, Clone, Serialize, Deserialize)]
+pub struct Quad<T = f32> {
+    pub rect: TexRect2D<T>,
+    /// Linear rgb with straight alpha.
+    pub color: Vec4<T>,
+}
+
+impl<'a> AsBytes<'a> for [Quad] {
+    fn as_bytes(&'a self) -> &'a [u8] {
+        unsafe { crate::util::as_u8_slice_from_slice(self) }
+    }
+}
+
+impl TexRect2D {
+    /// The shader fills quads with negative uvs instead of sampling the atlas,
+    /// for underlines and other solid rectangles.
+    pub fn solid(start: Vec2, end: Vec2) -> Self {
+        Self {
+            start,
+            end,
+            start_uv: Vec2::new(-1.0, -1.0),
+            end_uv: Vec2::new(-1.0, -1.0),
+        }
+    }
+}
+
+/// The colour the shader wants, it blends in linear space.
+fn shader_color(color: Srgb, alpha: f32) -> Vec4 {
+    let LinearRgb { r, g, b } = color.into();
+
+    Vec4::new(r, g, b, alpha)
+}
+
+impl<T: Add<Output = T> + Copy> Add<Vec2<T>> for TexRect2D<T> {
+    type Output = TexRect2D<T>;
+
+    fn add(self, rhs: Vec2<T>) -> Self::Output {
+        TexRect2D {
+            start: self.start + rhs,
+            end: self.end + rhs,
+            start_uv: self.start_uv,
+            end_uv: self.end_uv,
+        }
+    }
+}
+
+pub struct Line<'a, T = f32> {
+    atlas: &'a Atlas,
+    quads: Vec<Quad<T>>,
+    color: Vec4<T>,
+    advance: T,
+    text: String,
+    hit: hit::HitLine,
+    fallback: Option<char>,
+}
+
+impl<'a> Line<'a> {
+    pub fn new(text: &str) -> Self {
+        Self::with_atlas(&ATLAS, text)
+    }
+
+    pub fn with_atlas(atlas: &'a Atlas, text: &str) -> Self {
+        let mut line = Line {
+            atlas,
+            quads: Vec::new(),
+            color: shader_color(Srgb::new(0.0, 0.0, 0.0), 1.0),
+            advance: 0.0,
+            text: String::new(),
+            hit: hit::HitLine::default(),
+            fallback: Some('?'),
+        };
+
+        line.set(text);
+
+        line
+    }
+
+    /// Like `with_atlas` but fails instead of drawing the fallback for missing characters.
+    pub fn try_new(atlas: &'a Atlas, text: &str) -> Result<Self, TextError> {
+        if let Some(c) = text.chars().find(|&c| atlas.char_data(c).is_none()) {
+            return Err(TextError::MissingChar(c));
+        }
+
+        Ok(Self::with_atlas(atlas, text))
+    }
+
+    /// Characters missing from the atlas are drawn as the fallback.
+    pub fn set(&mut self, text: &str) {
+        self.quads.clear();
+        self.advance = 0.0;
+        self.text.clear();
+        self.hit.clear();
+
+        self.push(text);
+    }
+
+    /// What to draw for characters missing from the atlas in text pushed after this,
+    /// `'?'` by default. `None` skips them, so does a fallback that is missing itself.
+    pub fn set_fallback(&mut self, fallback: Option<char>) {
+        self.fallback = fallback;
+    }
+
+    /// The colour of text pushed after this, black by default.
+    pub fn set_color(&mut self, color: Srgb, alpha: f32) {
+        self.color = shader_color(color, alpha);
+    }
+
+    /// Shapes `text` when the atlas was built from a font, with `atlas::Builder`,
+    /// so kerning, ligatures and marks are placed by the font. Runs that need a
+    /// glyph the atlas does not have, like a ligature of characters outside it,
+    /// are laid out a character at a time instead, as is all text in atlases
+    /// without a font like the pre-baked one.
+    pub fn push(&mut self, text: &str) {
+        let atlas = self.atlas;
+
+        let Some(font) = atlas.font() else {
+            self.push_with(text, |c| atlas.char_data(c));
+            return;
+        };
+
+        // font units to line units, which are pixels over the atlas width
+        let scale = atlas.data.font_size / font.units_per_em() / atlas.data.size.x;
+        let start = self.text.len();
+        self.text.push_str(text);
+        self.hit.range.end = self.text.len();
+
+        for (run, rtl) in bidi::visual_runs(text) {
+            let shaped = shape::shape_run(font, text, run.clone(), rtl);
+
+            if shaped
+                .iter()
+                .all(|shaped| atlas.glyph_data(shaped.glyph).is_some())
+            {
+                self.push_shaped_run(start, &shaped, rtl, scale, |glyph| atlas.glyph_data(glyph));
+            } else {
+                self.push_run(start, text, run, rtl, |c| atlas.char_data(c));
+            }
+        }
+    }
+
+    /// Appends `text` with glyphs from somewhere other than the line's atlas,
+    /// like `GlyphCache::char_data`, without shaping it. Right to left text is put
+    /// in display order first. Characters `glyph` has nothing for are drawn as the fallback.
+    pub fn push_with(&mut self, text: &str, mut glyph: impl FnMut(char) -> Option<CharData>) {
+        let start = self.text.len();
+        self.text.push_str(text);
+        self.hit.range.end = self.text.len();
+
+        for (run, rtl) in bidi::visual_runs(text) {
+            self.push_run(start, text, run, rtl, &mut glyph);
+        }
+    }
+
+    /// Appends shaped `text` so kerning, ligatures and marks are placed by the font.
+    /// The glyphs come from `cache`, which rasterizes any glyph the shaper picks.
+    pub fn push_shaped(
+        &mut self,
+        cache: &mut cache::GlyphCache,
+        queue: &wgpu::Queue,
+        font: &font::Font,
+        font_size: f32,
+        text: &str,
+    ) {
+        // font units to line units, which are pixels over the atlas width
+        let scale = font_size / font.units_per_em() / cache.size().x;
+        let start = self.text.len();
+        self.text.push_str(text);
+        self.hit.range.end = self.text.len();
+
+        for (run, rtl) in bidi::visual_runs(text) {
+            let shaped = shape::shape_run(font, text, run, rtl);
+
+            self.push_shaped_run(start, &shaped, rtl, scale, |glyph| {
+                cache.glyph_data(queue, font, font_size, glyph)
+            });
+        }
+    }
+
+    /// Lays out the characters of one bidi run of `text`, which starts at `start` in the line.
+    fn push_run(
+        &mut self,
+        start: usize,
+        text: &str,
+        run: Range<usize>,
+        rtl: bool,
+        mut glyph: impl FnMut(char) -> Option<CharData>,
+    ) {
+        let mut chars: Vec<_> = text[run.clone()].char_indices().collect();
+        if rtl {
+            chars.reverse();
+        }
+
+        for (i, c) in chars {
+            let Some(data) = glyph(c).or_else(|| self.fallback.and_then(&mut glyph)) else {
+                continue;
+            };
+
+            self.quads.push(Quad {
+                rect: data.tex_rect + Vec2::new(self.advance, 0.0),
+                color: self.color,
+            });
+            self.hit.push(hit::Extent {
+                cluster: start + run.start + i,
+                x: self.advance,
+                advance: data.advance,
+                rtl,
+            });
+            self.advance += data.advance;
+        }
+    }
+
+    /// Places the output of the shaper for text that starts at `start` in the line,
+    /// `scale` takes font units to line units.
+    fn push_shaped_run(
+        &mut self,
+        start: usize,
+        shaped: &[shape::ShapedGlyph],
+        rtl: bool,
+        scale: f32,
+        mut glyph: impl FnMut(font::GlyphId) -> Option<CharData>,
+    ) {
+        for shaped in shaped {
+            if let Some(data) = glyph(shaped.glyph) {
+                let pen = Vec2::new(self.advance, 0.0) + shaped.offset * scale;
+
+                self.quads.push(Quad {
+                    rect: data.tex_rect + pen,
+                    color: self.color,
+                });
+            }
+
+            let advance = shaped.advance.x * scale;
+            self.hit.push(hit::Extent {
+                cluster: start + shaped.cluster as usize,
+                x: self.advance,
+                advance,
+                rtl,
+            });
+            self.advance += advance;
+        }
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// How far the pen moved, the width of the line ignoring overhanging glyphs.
+    pub fn advance(&self) -> f32 {
+        self.advance
+    }
+
+    /// The area covered by the line's quads, `None` when nothing is drawn.
+    pub fn bounds(&self) -> Option<Aabb2> {
+        self.glyph_rects().reduce(Aabb2::union)
+    }
+
+    /// The drawn rectangle of every glyph from left to right.
+    pub fn glyph_rects(&self) -> impl Iterator<Item = Aabb2> + '_ {
+        self.quads
+            .iter()
+            .map(|quad| Aabb2::from_corners(quad.rect.start, quad.rect.end))
+    }
+
+    /// The x of the caret in front of the byte at `index` of `text`.
+    pub fn caret_x(&self, index: usize) -> f32 {
+        hit::caret_x(&self.text, &self.hit, 0.0, index)
+    }
+
+    /// The byte index of the caret closest to `x`.
+    pub fn index_at(&self, x: f32) -> usize {
+        hit::index_at(&self.text, &self.hit, x)
+    }
+
+    /// The horizontal spans covering the bytes in `selection`.
+    pub fn selection(&self, selection: Range<usize>) -> Vec<Range<f32>> {
+        hit::selection(&self.text, &self.hit, selection)
+    }
+
+    pub fn quads(&self) -> &[Quad] {
+        &self.quads
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        self.quads.as_bytes()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn built_in_atlas_loads() {
+        assert_eq!(ATLAS.encoding(), atlas::Encoding::Coverage);
+        assert!((' '..='~').all(|c| ATLAS.char_data(c).is_some()));
+    }
+
+    #[test]
+    fn missing_characters() {
+        assert!(matches!(
+            Line::try_new(&ATLAS, "a\u{1F600}"),
+            Err(TextError::MissingChar('\u{1F600}'))
+        ));
+
+        // the fallback takes the place of the missing character
+        let line = Line::new("a\u{1F600}");
+        let mut without = Line::new("a\u{1F600}");
+        without.set_fallback(None);
+        without.set("a\u{1F600}");
+
+        assert_eq!(line.quads().len(), 2);
+        assert_eq!(without.quads().len(), 1);
+    }
+
+    #[test]
+    fn errors_keep_their_source() {
+        use std::error::Error;
+
+        let image = Atlas::from_bytes(b"not a png", include_str!("../atlas.json"))
+            .err()
+            .expect("the image is invalid");
+        assert!(matches!(image, TextError::Image(_)));
+        assert!(image.source().unwrap().is::<image::ImageError>());
+
+        let json = Atlas::from_bytes(include_bytes!("../atlas.png"), "{")
+            .err()
+            .expect("the json is invalid");
+        assert!(matches!(
+            json,
+            TextError::Json(rtexpacker::JsonError::Parse(_))
+        ));
+        let source = json.source().unwrap();
+        assert!(source.is::<rtexpacker::JsonError>());
+        assert!(source.source().unwrap().is::<serde_json::Error>());
+
+        let font =
+            TextError::from(font::Font::from_bytes(b"not a font".as_slice(), 0).unwrap_err());
+        assert!(font.source().unwrap().is::<ttf_parser::FaceParsingError>());
+
+        let missing = TextError::MissingChar('\u{1F600}');
+        assert!(missing.source().is_none());
+        assert_eq!(
+            missing.to_string(),
+            "the atlas has no glyph for '\u{1f600}'"
+        );
+    }
+}
@@ -0,0 +1,228 @@
+use crate::math::aabb::Aabb2;
+use crate::math::bezier::{CubicBezier, QuadBezier};
+use crate::math::sdf::FillRule;
+use crate::math::vector::Vec2;
+use crate::util::{as_u8_slice_from_slice, AsBytes};
+
+/// A line of a glyph outline, the `Segment` struct in `font_renderer.wgsl`.
+#[repr(C)]
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Segment {
+    pub a: Vec2,
+    pub b: Vec2,
+}
+
+impl<'a> AsBytes<'a> for [Segment] {
+    fn as_bytes(&'a self) -> &'a [u8] {
+        unsafe { as_u8_slice_from_slice(self) }
+    }
+}
+
+/// A glyph outline flattened into closed polygons.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GlyphOutline {
+    /// The points of every contour, one after another.
+    pub points: Vec<Vec2>,
+    /// The end of each contour in `points`, exclusive. Every contour is closed so
+    /// it has as many segments as points and the markers index `segments()` too.
+    pub contour_markers: Vec<u32>,
+    pub bounds: Aabb2,
+}
+
+impl GlyphOutline {
+    pub fn contours(&self) -> impl Iterator<Item = &[Vec2]> {
+        let mut start = 0;
+
+        self.contour_markers.iter().map(move |&end| {
+            let contour = &self.points[start..end as usize];
+            start = end as usize;
+            contour
+        })
+    }
+
+    /// Every edge including the ones closing the contours, ready for `font_renderer.wgsl`.
+    pub fn segments(&self) -> Vec<Segment> {
+        self.contours()
+            .flat_map(|contour| {
+                let last = contour.last().copied();
+
+                contour.iter().scan(last, |a, &b| {
+                    let segment = Segment {
+                        a: a.replace(b)?,
+                        b,
+                    };
+
+                    Some(segment)
+                })
+            })
+            .collect()
+    }
+
+    /// Signed distance to the outline, negative inside, using the non zero rule like ttf.
+    pub fn distance(&self, point: Vec2) -> f32 {
+        point.path(self.contours(), FillRule::NonZero)
+    }
+}
+
+/// Receives a glyph outline from ttf-parser and flattens the curves.
+pub(super) struct OutlineCollector {
+    scale: f32,
+    tolerance: f32,
+    points: Vec<Vec2>,
+    contour_markers: Vec<u32>,
+    start: usize,
+}
+
+impl OutlineCollector {
+    pub fn new(scale: f32, tolerance: f32) -> Self {
+        Self {
+            scale,
+            tolerance,
+            points: Vec::new(),
+            contour_markers: Vec::new(),
+            start: 0,
+        }
+    }
+
+    fn point(&self, x: f32, y: f32) -> Vec2 {
+        Vec2::new(x, y) * self.scale
+    }
+
+    fn last(&self) -> Vec2 {
+        self.points.last().copied().unwrap_or(Vec2::new(0.0, 0.0))
+    }
+
+    pub fn finish(mut self) -> GlyphOutline {
+        self.end_contour();
+
+        let bounds = Aabb2::from_points(self.points.iter().copied())
+            .unwrap_or(Aabb2::new(Vec2::new(0.0, 0.0), Vec2::new(0.0, 0.0)));
+
+        GlyphOutline {
+            points: self.points,
+            contour_markers: self.contour_markers,
+            bounds,
+        }
+    }
+
+    fn end_contour(&mut self) {
+        // the closing edge is implied, drop the point that duplicates the start
+        if self.points.len() > self.start + 1 && self.points.last() == self.points.get(self.start) {
+            self.points.pop();
+        }
+
+        if self.points.len() > self.start {
+            #[allow(clippy::cast_possible_truncation)]
+            self.contour_markers.push(self.points.len() as u32);
+        }
+
+        self.start = self.points.len();
+    }
+}
+
+impl ttf_parser::OutlineBuilder for OutlineCollector {
+    fn move_to(&mut self, x: f32, y: f32) {
+        self.end_contour();
+        self.points.push(self.point(x, y));
+    }
+
+    fn line_to(&mut self, x: f32, y: f32) {
+        self.points.push(self.point(x, y));
+    }
+
+    fn quad_to(&mut self, x1: f32, y1: f32, x: f32, y: f32) {
+        let curve = QuadBezier::new(self.last(), self.point(x1, y1), self.point(x, y));
+
+        curve.flatten(self.tolerance, |_, b| self.points.push(b));
+    }
+
+    fn curve_to(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, x: f32, y: f32) {
+        let curve = CubicBezier::new(
+            self.last(),
+            self.point(x1, y1),
+            self.point(x2, y2),
+            self.point(x, y),
+        );
+
+        curve.flatten(self.tolerance, |_, b| self.points.push(b));
+    }
+
+    fn close(&mut self) {
+        self.end_contour();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::math::vector::Vec2;
+    use crate::text::test_font;
+
+    const TOLERANCE: f32 = 1.0 / 1024.0;
+
+    #[test]
+    fn outline_of_o() {
+        let font = test_font();
+        let glyph = font.glyph_id('O').expect("the font has an O");
+        let scale = 1.0 / font.units_per_em();
+        let outline = font
+            .outline(glyph, scale, TOLERANCE)
+            .expect("O has an outline");
+
+        // the outside and the hole
+        assert_eq!(outline.contour_markers.len(), 2);
+        assert_eq!(outline.contours().count(), 2);
+
+        // the extremes of an O are on curve points, so flattening keeps the bounds
+        let bounds = font.glyph_bounds(glyph).expect("O has bounds");
+        for (a, b) in [
+            (outline.bounds.min, bounds.min * scale),
+            (outline.bounds.max, bounds.max * scale),
+        ] {
+            assert!((a - b).len() < TOLERANCE, "{a:?} is not {b:?}");
+        }
+
+        let center = (outline.bounds.min + outline.bounds.max) * 0.5;
+        let stroke = Vec2::new(outline.bounds.min.x + 0.02, center.y);
+        let outside = Vec2::new(outline.bounds.min.x - 0.02, center.y);
+
+        assert!(
+            outline.distance(center) > 0.0,
+            "the hole is outside the glyph"
+        );
+        assert!(outline.distance(stroke) < 0.0);
+        assert!(outline.distance(outside) > 0.0);
+        assert!((outline.distance(outside) - 0.02).abs() < TOLERANCE);
+    }
+
+    #[test]
+    fn segments_close_every_contour() {
+        let font = test_font();
+        let glyph = font.glyph_id('B').expect("the font has a B");
+        let outline = font
+            .outline(glyph, 1.0 / font.units_per_em(), TOLERANCE)
+            .expect("B has an outline");
+        let segments = outline.segments();
+
+        assert_eq!(segments.len(), outline.points.len());
+
+        let mut start = 0;
+        for &end in &outline.contour_markers {
+            let contour = &segments[start..end as usize];
+
+            // every segment starts where the one before it ends, wrapping around
+            for (before, segment) in contour.iter().zip(contour.iter().cycle().skip(1)) {
+                assert_eq!(before.b, segment.a);
+            }
+
+            start = end as usize;
+        }
+    }
+
+    #[test]
+    fn space_has_no_outline() {
+        let font = test_font();
+        let space = font.glyph_id(' ').expect("the font has a space");
+
+        assert!(font.outline(space, 1.0, TOLERANCE).is_none());
+    }
+}
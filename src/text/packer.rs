@@ -0,0 +1,251 @@
+/// Packs rectangles into rows of similar height.
+/// Simple and fast, works well for glyphs since most have about the same height.
+#[derive(Debug, Clone)]
+pub struct ShelfPacker {
+    width: u32,
+    height: u32,
+    shelves: Vec<Shelf>,
+}
+
+#[derive(Debug, Clone)]
+struct Shelf {
+    y: u32,
+    height: u32,
+    x: u32,
+    /// Deallocated spans as `(x, width)` that can be handed out again.
+    free: Vec<(u32, u32)>,
+}
+
+impl Shelf {
+    fn is_empty(&self) -> bool {
+        self.x == 0 && self.free.is_empty()
+    }
+}
+
+impl ShelfPacker {
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            shelves: Vec::new(),
+        }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// The height of the area that has rectangles in it.
+    pub fn used_height(&self) -> u32 {
+        self.shelves
+            .last()
+            .map_or(0, |shelf| shelf.y + shelf.height)
+    }
+
+    /// Returns the top left corner of the allocated rectangle or `None` when it does not fit.
+    pub fn allocate(&mut self, width: u32, height: u32) -> Option<(u32, u32)> {
+        if width > self.width {
+            return None;
+        }
+
+        // reuse freed space before growing a shelf, the tightest fit wastes the least
+        let freed = self
+            .shelves
+            .iter_mut()
+            .filter(|shelf| shelf.height >= height)
+            .flat_map(|shelf| {
+                let y = shelf.y;
+                let height = shelf.height;
+                shelf
+                    .free
+                    .iter_mut()
+                    .filter(|(_, span)| *span >= width)
+                    .map(move |span| (height, y, span))
+            })
+            .min_by_key(|(height, _, (_, span))| (*height, *span));
+
+        if let Some((_, y, (x, span))) = freed {
+            let position = (*x, y);
+            *x += width;
+            *span -= width;
+            self.shelves.iter_mut().for_each(|shelf| {
+                shelf.free.retain(|&(_, span)| span > 0);
+            });
+            return Some(position);
+        }
+
+        let best = self
+            .shelves
+            .iter_mut()
+            .filter(|shelf| {
+                !shelf.is_empty() && shelf.height >= height && self.width - shelf.x >= width
+            })
+            .min_by_key(|shelf| shelf.height);
+
+        if let Some(shelf) = best {
+            let position = (shelf.x, shelf.y);
+            shelf.x += width;
+            return Some(position);
+        }
+
+        // an emptied shelf is cut down to the height needed, the rest stays empty
+        let empty = self
+            .shelves
+            .iter()
+            .position(|shelf| shelf.is_empty() && shelf.height >= height);
+
+        if let Some(index) = empty {
+            let emptied = &mut self.shelves[index];
+            let rest = Shelf {
+                y: emptied.y + height,
+                height: emptied.height - height,
+                x: 0,
+                free: Vec::new(),
+            };
+
+            emptied.height = height;
+            emptied.x = width;
+            let y = emptied.y;
+
+            if rest.height > 0 {
+                self.shelves.insert(index + 1, rest);
+            }
+
+            return Some((0, y));
+        }
+
+        let y = self.used_height();
+
+        if self.height - y < height {
+            return None;
+        }
+
+        self.shelves.push(Shelf {
+            y,
+            height,
+            x: width,
+            free: Vec::new(),
+        });
+
+        Some((0, y))
+    }
+
+    /// Frees a rectangle returned by `allocate`, `width` has to be the allocated width.
+    pub fn deallocate(&mut self, x: u32, y: u32, width: u32) {
+        let Some(row) = self.shelves.iter_mut().find(|shelf| shelf.y == y) else {
+            return;
+        };
+
+        row.free.push((x, width));
+
+        // merge neighbouring spans and give the one at the end back to the shelf
+        row.free.sort_unstable();
+        row.free
+            .dedup_by(|(start, span), (before_start, before_span)| {
+                let touching = *before_start + *before_span == *start;
+                if touching {
+                    *before_span += *span;
+                }
+                touching
+            });
+
+        if let Some(&(x, width)) = row.free.last() {
+            if x + width == row.x {
+                row.x = x;
+                row.free.pop();
+            }
+        }
+
+        if row.is_empty() {
+            self.reclaim();
+        }
+    }
+
+    /// Merges neighbouring empty shelves so taller rectangles fit in them
+    /// and drops the ones at the bottom so the space can be used by any shelf.
+    fn reclaim(&mut self) {
+        self.shelves.dedup_by(|shelf, before| {
+            let both = before.is_empty() && shelf.is_empty();
+            if both {
+                before.height += shelf.height;
+            }
+            both
+        });
+
+        while self.shelves.last().is_some_and(Shelf::is_empty) {
+            self.shelves.pop();
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.shelves.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn freed_shelves_fit_taller_rectangles() {
+        let mut packer = ShelfPacker::new(100, 20);
+        let slots: Vec<_> = (0..4)
+            .map(|_| packer.allocate(50, 10).expect("four slots fit"))
+            .collect();
+
+        assert_eq!(packer.allocate(10, 15), None);
+
+        for (x, y) in slots {
+            packer.deallocate(x, y, 50);
+        }
+
+        assert_eq!(packer.used_height(), 0);
+        assert_eq!(packer.allocate(10, 15), Some((0, 0)));
+    }
+
+    #[test]
+    fn emptied_shelf_between_others_is_reused() {
+        let mut packer = ShelfPacker::new(100, 30);
+        let top = packer.allocate(100, 10).unwrap();
+        let middle = packer.allocate(60, 10).unwrap();
+        let bottom = packer.allocate(100, 10).unwrap();
+
+        packer.deallocate(middle.0, middle.1, 60);
+
+        // cut down to 4 pixels, leaving 6 for the next one
+        assert_eq!(packer.allocate(100, 4), Some((0, 10)));
+        assert_eq!(packer.allocate(100, 6), Some((0, 14)));
+        assert_eq!(packer.allocate(1, 1), None);
+        assert_eq!((top, bottom), ((0, 0), (0, 20)));
+    }
+
+    #[test]
+    fn neighbouring_empty_shelves_merge() {
+        let mut packer = ShelfPacker::new(100, 30);
+        let a = packer.allocate(100, 10).unwrap();
+        let b = packer.allocate(100, 10).unwrap();
+        packer.allocate(100, 10).unwrap();
+
+        packer.deallocate(a.0, a.1, 100);
+        packer.deallocate(b.0, b.1, 100);
+
+        assert_eq!(packer.allocate(100, 20), Some((0, 0)));
+    }
+
+    #[test]
+    fn freed_spans_are_reused() {
+        let mut packer = ShelfPacker::new(100, 10);
+        let a = packer.allocate(30, 10).unwrap();
+        packer.allocate(30, 10).unwrap();
+
+        packer.deallocate(a.0, a.1, 30);
+
+        assert_eq!(packer.allocate(20, 10), Some((0, 0)));
+        assert_eq!(packer.allocate(40, 10), Some((60, 0)));
+        assert_eq!(packer.allocate(20, 10), None);
+    }
+}
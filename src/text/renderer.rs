@@ -0,0 +1,352 @@
+use super::{atlas, Atlas, Quad};
+use crate::render_pipeline;
+use crate::util::AsBytes;
+use std::collections::HashMap;
+
+/// Draws quads from `Line` and `Paragraph` with `uv_tris.wgsl`.
+/// Queue the text for a frame, `prepare` it and then `render` it in a pass.
+pub struct TextRenderer {
+    pipeline: wgpu::RenderPipeline,
+    view: wgpu::TextureView,
+    sampler: wgpu::Sampler,
+    buffer: wgpu::Buffer,
+    bind_group: wgpu::BindGroup,
+    quads: Vec<Quad>,
+    /// Quads in the buffer, can be less than `quads` until `prepare` is called.
+    drawn: u32,
+    dirty: bool,
+}
+
+impl TextRenderer {
+    /// Quads the buffer has room for at first, storage bindings can not be empty.
+    const MIN_CAPACITY: u64 = 64;
+
+    /// Uploads `atlas` to a texture for text made with `Line::new` and `Line::with_atlas`.
+    pub fn new(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        format: wgpu::TextureFormat,
+        atlas: &Atlas,
+        atlas_format: atlas::Format,
+    ) -> Self {
+        let texture = atlas.create_texture(device, queue, atlas_format);
+
+        Self::with_texture(device, format, &texture, atlas.encoding())
+    }
+
+    /// Draws from a texture the renderer does not upload itself, like `GlyphCache::texture`.
+    /// It has to be in one of the `atlas::Format`s.
+    pub fn with_texture(
+        device: &wgpu::Device,
+        format: wgpu::TextureFormat,
+        texture: &wgpu::Texture,
+        encoding: atlas::Encoding,
+    ) -> Self {
+        let module = &device.create_shader_module(wgpu::include_wgsl!("../uv_tris.wgsl"));
+
+        let atlas_format =
+            atlas::Format::of(texture.format()).expect("atlas textures are R8 or RGBA8");
+        let constants = HashMap::from([
+            (
+                "glyph_channel".to_owned(),
+                f64::from(atlas_format.glyph_channel()),
+            ),
+            (
+                "distance_field".to_owned(),
+                f64::from(u8::from(encoding == atlas::Encoding::DistanceField)),
+            ),
+        ]);
+
+        let pipeline = render_pipeline::Builder::new()
+            .add_device(device)
+            .add_label("text render pipeline")
+            .add_vertex(wgpu::VertexState {
+                module,
+                entry_point: Some("vs_main"),
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+                buffers: &[],
+            })
+            .add_primitive(wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleStrip,
+                ..Default::default()
+            })
+            .add_fragment(wgpu::FragmentState {
+                module,
+                entry_point: Some("fs_main"),
+                compilation_options: wgpu::PipelineCompilationOptions {
+                    constants: &constants,
+                    ..Default::default()
+                },
+                targets: &[Some(wgpu::ColorTargetState {
+                    format,
+                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            })
+            .build();
+
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("sampler linear"),
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            address_mode_w: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            mipmap_filter: wgpu::FilterMode::Nearest,
+            ..Default::default()
+        });
+
+        let buffer = Self::create_buffer(device, Self::MIN_CAPACITY);
+        let bind_group = Self::create_bind_group(device, &pipeline, &view, &sampler, &buffer);
+
+        Self {
+            pipeline,
+            view,
+            sampler,
+            buffer,
+            bind_group,
+            quads: Vec::new(),
+            drawn: 0,
+            dirty: false,
+        }
+    }
+
+    /// Adds quads to draw, they stay queued until `clear`.
+    pub fn queue_text(&mut self, quads: &[Quad]) {
+        self.quads.extend_from_slice(quads);
+        self.dirty = true;
+    }
+
+    pub fn clear(&mut self) {
+        self.quads.clear();
+        self.dirty = true;
+    }
+
+    /// Writes the queued quads to the buffer if they changed, growing it when they do not fit.
+    pub fn prepare(&mut self, device: &wgpu::Device, queue: &wgpu::Queue) {
+        if !self.dirty {
+            return;
+        }
+
+        let bytes = self.quads.as_bytes();
+
+        if bytes.len() as u64 > self.buffer.size() {
+            let capacity = (self.quads.len() as u64).next_power_of_two();
+
+            self.buffer = Self::create_buffer(device, capacity);
+            self.bind_group = Self::create_bind_group(
+                device,
+                &self.pipeline,
+                &self.view,
+                &self.sampler,
+                &self.buffer,
+            );
+        }
+
+        queue.write_buffer(&self.buffer, 0, bytes);
+
+        self.drawn = u32::try_from(self.quads.len()).expect("fewer than 4 billion quads");
+        self.dirty = false;
+    }
+
+    /// Draws what was in the queue at the last `prepare`.
+    pub fn render(&self, pass: &mut wgpu::RenderPass) {
+        if self.drawn == 0 {
+            return;
+        }
+
+        pass.set_pipeline(&self.pipeline);
+        pass.set_bind_group(0, &self.bind_group, &[]);
+        pass.draw(0..4, 0..self.drawn);
+    }
+
+    fn create_buffer(device: &wgpu::Device, capacity: u64) -> wgpu::Buffer {
+        device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("text quad buffer"),
+            size: capacity * std::mem::size_of::<Quad>() as u64,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        })
+    }
+
+    fn create_bind_group(
+        device: &wgpu::Device,
+        pipeline: &wgpu::RenderPipeline,
+        view: &wgpu::TextureView,
+        sampler: &wgpu::Sampler,
+        buffer: &wgpu::Buffer,
+    ) -> wgpu::BindGroup {
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("text bind group"),
+            layout: &pipeline.get_bind_group_layout(0),
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(sampler),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: buffer.as_entire_binding(),
+                },
+            ],
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::vector::{Vec2, Vec4};
+    use crate::text::{TexRect2D, ATLAS};
+
+    const SIZE: u32 = 64;
+    const FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8Unorm;
+
+    /// A solid quad from `start` to `end` in clip space.
+    fn solid(start: Vec2, end: Vec2, color: Vec4) -> Quad {
+        Quad {
+            rect: TexRect2D::solid(start, end),
+            color,
+        }
+    }
+
+    /// Renders what was prepared into a cleared target and reads back its pixels, top row first.
+    fn draw(device: &wgpu::Device, queue: &wgpu::Queue, text: &TextRenderer) -> Vec<Vec<[u8; 4]>> {
+        let target = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("text renderer test target"),
+            size: wgpu::Extent3d {
+                width: SIZE,
+                height: SIZE,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: FORMAT,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[],
+        });
+
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+        {
+            let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: &target.create_view(&wgpu::TextureViewDescriptor::default()),
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                ..Default::default()
+            });
+            text.render(&mut pass);
+        }
+        queue.submit([encoder.finish()]);
+
+        crate::util::read_texture(device, queue, &target)
+            .iter()
+            .map(|row| {
+                row.chunks(4)
+                    .map(|pixel| pixel.try_into().expect("pixels are 4 bytes"))
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn draws_queued_quads() {
+        let Ok((_instance, device, queue)) = crate::util::get_gpu() else {
+            eprintln!("no gpu adapter, skipping");
+            return;
+        };
+
+        let mut text = TextRenderer::new(&device, &queue, FORMAT, &ATLAS, atlas::Format::R8);
+
+        // the bottom left quarter, y is up in clip space
+        text.queue_text(&[solid(
+            Vec2::new(-1.0, -1.0),
+            Vec2::new(0.0, 0.0),
+            Vec4::new(1.0, 0.0, 0.0, 1.0),
+        )]);
+
+        // nothing is drawn until it is prepared
+        let pixels = draw(&device, &queue, &text);
+        assert!(pixels.iter().flatten().all(|&pixel| pixel == [0; 4]));
+
+        text.prepare(&device, &queue);
+        let pixels = draw(&device, &queue, &text);
+
+        assert_eq!(pixels[48][16], [255, 0, 0, 255]);
+        assert_eq!(pixels[16][16], [0; 4]);
+        assert_eq!(pixels[48][48], [0; 4]);
+
+        text.clear();
+        text.prepare(&device, &queue);
+        let pixels = draw(&device, &queue, &text);
+        assert!(pixels.iter().flatten().all(|&pixel| pixel == [0; 4]));
+    }
+
+    #[test]
+    #[allow(clippy::cast_precision_loss)]
+    fn grows_past_its_first_buffer() {
+        let Ok((_instance, device, queue)) = crate::util::get_gpu() else {
+            eprintln!("no gpu adapter, skipping");
+            return;
+        };
+
+        let mut text = TextRenderer::new(&device, &queue, FORMAT, &ATLAS, atlas::Format::R8);
+
+        // columns a pixel wide each, one more than the buffer starts with
+        let quads: Vec<_> = (0..SIZE)
+            .map(|x| {
+                let left = x as f32 / SIZE as f32 * 2.0 - 1.0;
+                solid(
+                    Vec2::new(left, -1.0),
+                    Vec2::new(left + 2.0 / SIZE as f32, 1.0),
+                    Vec4::new(0.0, 1.0, 0.0, 1.0),
+                )
+            })
+            .collect();
+        text.queue_text(&quads);
+        text.queue_text(&quads[..1]);
+        assert!(text.quads.len() as u64 > TextRenderer::MIN_CAPACITY);
+
+        text.prepare(&device, &queue);
+
+        let pixels = draw(&device, &queue, &text);
+        assert!(pixels
+            .iter()
+            .flatten()
+            .all(|&pixel| pixel == [0, 255, 0, 255]));
+    }
+
+    #[test]
+    fn draws_text_from_the_atlas() {
+        let Ok((_instance, device, queue)) = crate::util::get_gpu() else {
+            eprintln!("no gpu adapter, skipping");
+            return;
+        };
+
+        let mut text = TextRenderer::new(&device, &queue, FORMAT, &ATLAS, atlas::Format::R8);
+        text.queue_text(crate::text::Line::new("Zane").quads());
+        text.prepare(&device, &queue);
+
+        // the glyphs have antialiased edges and solid insides
+        let alpha: Vec<u8> = draw(&device, &queue, &text)
+            .iter()
+            .flatten()
+            .map(|pixel| pixel[3])
+            .collect();
+
+        assert!(alpha.contains(&255));
+        assert!(alpha.iter().any(|&a| a > 0 && a < 255));
+        assert!(alpha.contains(&0));
+    }
+}
@@ -0,0 +1,71 @@
+use super::font::Font;
+use crate::color::Srgb;
+use std::ops::Range;
+
+/// How a span of rich text looks.
+#[derive(Debug, Clone)]
+pub struct Style {
+    pub font: Font,
+    /// Pixels per em.
+    pub font_size: f32,
+    pub color: Srgb,
+    pub alpha: f32,
+    pub underline: bool,
+    pub strikethrough: bool,
+}
+
+impl Style {
+    /// Opaque black without decorations.
+    pub fn new(font: &Font, font_size: f32) -> Self {
+        Self {
+            font: font.clone(),
+            font_size,
+            color: Srgb::new(0.0, 0.0, 0.0),
+            alpha: 1.0,
+            underline: false,
+            strikethrough: false,
+        }
+    }
+
+    /// Pixels per font unit.
+    pub fn scale(&self) -> f32 {
+        self.font_size / self.font.units_per_em()
+    }
+
+    /// The ascender in pixels.
+    pub fn ascender(&self) -> f32 {
+        let (ascender, _, _) = self.font.vertical_metrics();
+
+        ascender * self.scale()
+    }
+}
+
+/// Text made of spans that each have their own style.
+#[derive(Debug, Clone, Default)]
+pub struct RichText {
+    text: String,
+    spans: Vec<(Range<usize>, Style)>,
+}
+
+impl RichText {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add_span(mut self, text: &str, style: Style) -> Self {
+        let start = self.text.len();
+        self.text.push_str(text);
+        self.spans.push((start..self.text.len(), style));
+
+        self
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.text
+    }
+
+    /// The byte range of every span in the text with its style.
+    pub fn spans(&self) -> &[(Range<usize>, Style)] {
+        &self.spans
+    }
+}
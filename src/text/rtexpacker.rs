@@ -0,0 +1,177 @@
+use super::atlas::PlacedGlyph;
+use super::{AtlasData, CharData};
+use crate::math::vector::Vec2;
+use serde::Deserialize;
+
+/// Why an rTexPacker sprite sheet could not be read as a font atlas.
+#[derive(Debug)]
+pub enum JsonError {
+    /// Not json or a field is missing or has the wrong type, the error says which.
+    Parse(serde_json::Error),
+    /// The sheet was exported from images instead of a font.
+    NotAFont,
+    InvalidChar {
+        sprite: String,
+        value: u32,
+    },
+    /// The sprite's rectangle goes past the edge of the atlas.
+    OutOfBounds {
+        sprite: String,
+    },
+    /// The json is for an atlas of another size than the image.
+    ImageSize {
+        json: (u32, u32),
+        image: (u32, u32),
+    },
+}
+
+impl std::fmt::Display for JsonError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            JsonError::Parse(error) => write!(f, "{error}"),
+            JsonError::NotAFont => write!(f, "the sheet was not exported from a font"),
+            JsonError::InvalidChar { sprite, value } => {
+                write!(f, "sprite {sprite:?} has the invalid char {value:#x}")
+            }
+            JsonError::OutOfBounds { sprite } => {
+                write!(f, "sprite {sprite:?} goes past the edge of the atlas")
+            }
+            JsonError::ImageSize { json, image } => write!(
+                f,
+                "the json is for a {}x{} atlas but the image is {}x{}",
+                json.0, json.1, image.0, image.1
+            ),
+        }
+    }
+}
+
+impl std::error::Error for JsonError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            JsonError::Parse(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<serde_json::Error> for JsonError {
+    fn from(error: serde_json::Error) -> Self {
+        JsonError::Parse(error)
+    }
+}
+
+#[derive(Deserialize)]
+struct Sheet {
+    atlas: Info,
+    sprites: Vec<Sprite>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Info {
+    width: u32,
+    height: u32,
+    is_font: bool,
+    font_size: f32,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Sprite {
+    name_id: String,
+    position: Point,
+    source_size: Size,
+    char: Char,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Char {
+    value: u32,
+    /// Top left of the glyph relative to the pen on the line's top, y down.
+    offset: Point,
+    #[serde(rename = "advanceX")]
+    advance: f32,
+}
+
+#[derive(Deserialize)]
+struct Point {
+    x: i32,
+    y: i32,
+}
+
+#[derive(Deserialize)]
+struct Size {
+    width: u32,
+    height: u32,
+}
+
+/// Reads the json rTexPacker exports next to a font atlas image.
+/// Pixels are converted to the units `CharData` uses, over the atlas width.
+#[allow(clippy::cast_precision_loss)]
+pub(super) fn parse(json: &str) -> Result<AtlasData, JsonError> {
+    let sheet: Sheet = serde_json::from_str(json)?;
+
+    if !sheet.atlas.is_font {
+        return Err(JsonError::NotAFont);
+    }
+
+    let size = Vec2::new(sheet.atlas.width as f32, sheet.atlas.height as f32);
+
+    let char_data = sheet
+        .sprites
+        .into_iter()
+        .map(|sprite| {
+            let Some(c) = char::from_u32(sprite.char.value) else {
+                return Err(JsonError::InvalidChar {
+                    sprite: sprite.name_id,
+                    value: sprite.char.value,
+                });
+            };
+
+            let (Ok(x), Ok(y)) = (
+                u32::try_from(sprite.position.x),
+                u32::try_from(sprite.position.y),
+            ) else {
+                return Err(JsonError::OutOfBounds {
+                    sprite: sprite.name_id,
+                });
+            };
+
+            let Size { width, height } = sprite.source_size;
+
+            if x.saturating_add(width) > sheet.atlas.width
+                || y.saturating_add(height) > sheet.atlas.height
+            {
+                return Err(JsonError::OutOfBounds {
+                    sprite: sprite.name_id,
+                });
+            }
+
+            // the offset is to the top of the glyph from the top of the line, y down
+            let offset = Vec2::new(
+                sprite.char.offset.x as f32,
+                -(sprite.char.offset.y as f32) - height as f32,
+            );
+
+            let glyph = PlacedGlyph {
+                x,
+                y,
+                width,
+                height,
+                offset,
+            };
+
+            Ok((
+                c.to_string(),
+                CharData::from_pixels(glyph, 0.0, sprite.char.advance, size),
+            ))
+        })
+        .collect::<Result<_, _>>()?;
+
+    Ok(AtlasData {
+        size,
+        font_size: sheet.atlas.font_size,
+        char_data,
+    })
+}
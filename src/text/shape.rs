@@ -0,0 +1,112 @@
+use super::font::{Font, GlyphId};
+use crate::math::vector::Vec2;
+use std::ops::Range;
+
+/// A glyph placed by the shaper, all distances are in font units.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ShapedGlyph {
+    pub glyph: GlyphId,
+    /// Byte index in the text of the first character this glyph came from.
+    /// Ligatures cover several characters and marks share their base's cluster.
+    pub cluster: u32,
+    /// How far to move the pen after this glyph.
+    pub advance: Vec2,
+    /// Where to draw the glyph relative to the pen, y up.
+    pub offset: Vec2,
+}
+
+/// Runs the font's `GSUB` and `GPOS` tables, or `kern` for older fonts, over `text`.
+/// Mixed direction text is split into runs by the unicode bidi algorithm and the
+/// glyphs come out in visual order, left to right.
+pub fn shape(font: &Font, text: &str) -> Vec<ShapedGlyph> {
+    super::bidi::visual_runs(text)
+        .into_iter()
+        .flat_map(|(run, rtl)| shape_run(font, text, run, rtl))
+        .collect()
+}
+
+/// Shapes one run of `text` in a single direction, with the rest of the text as
+/// context. Right to left runs come out in visual order too, so their clusters go down.
+#[allow(clippy::cast_precision_loss)]
+pub fn shape_run(font: &Font, text: &str, run: Range<usize>, rtl: bool) -> Vec<ShapedGlyph> {
+    let face = rustybuzz::Face::from_face(font.face());
+
+    let mut buffer = rustybuzz::UnicodeBuffer::new();
+    buffer.set_pre_context(&text[..run.start]);
+    buffer.push_str(&text[run.clone()]);
+    buffer.set_post_context(&text[run.end..]);
+    buffer.guess_segment_properties();
+    buffer.set_direction(if rtl {
+        rustybuzz::Direction::RightToLeft
+    } else {
+        rustybuzz::Direction::LeftToRight
+    });
+
+    let output = rustybuzz::shape(&face, &[], buffer);
+    let offset = u32::try_from(run.start).expect("text is shorter than 4GiB");
+
+    output
+        .glyph_infos()
+        .iter()
+        .zip(output.glyph_positions())
+        .map(|(info, position)| ShapedGlyph {
+            glyph: GlyphId(u16::try_from(info.glyph_id).unwrap_or(0)),
+            cluster: info.cluster + offset,
+            advance: Vec2::new(position.x_advance as f32, position.y_advance as f32),
+            offset: Vec2::new(position.x_offset as f32, position.y_offset as f32),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::text::test_font;
+
+    #[test]
+    fn kerning() {
+        let font = test_font();
+        let glyphs = shape(&font, "AV");
+        let a = font.glyph_id('A').expect("the font has an A");
+
+        assert_eq!(glyphs.len(), 2);
+        assert_eq!(glyphs[0].glyph, a);
+        assert!(
+            glyphs[0].advance.x < font.advance(a),
+            "A is kerned towards V"
+        );
+    }
+
+    #[test]
+    fn ligature() {
+        let font = test_font();
+        let glyphs = shape(&font, "ffi");
+
+        assert_eq!(glyphs.len(), 1);
+        assert_eq!(glyphs[0].cluster, 0);
+        assert_ne!(Some(glyphs[0].glyph), font.glyph_id('f'));
+    }
+
+    #[test]
+    // advances are whole font units
+    #[allow(clippy::float_cmp)]
+    fn marks_share_their_base_cluster() {
+        let font = test_font();
+        // an x with a combining acute accent, which has no precomposed form
+        let glyphs = shape(&font, "x\u{301}y");
+
+        let clusters: Vec<_> = glyphs.iter().map(|glyph| glyph.cluster).collect();
+        assert_eq!(clusters, [0, 0, 3]);
+        assert_eq!(glyphs[1].advance.x, 0.0);
+    }
+
+    #[test]
+    fn right_to_left_runs_come_out_in_visual_order() {
+        let font = test_font();
+        let glyphs = shape(&font, "ab \u{5d0}\u{5d1}\u{5d2} c");
+
+        let clusters: Vec<_> = glyphs.iter().map(|glyph| glyph.cluster).collect();
+        assert_eq!(clusters, [0, 1, 2, 7, 5, 3, 9, 10]);
+        assert_eq!(Some(glyphs[3].glyph), font.glyph_id('\u{5d2}'));
+    }
+}
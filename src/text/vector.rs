@@ -0,0 +1,477 @@
+use super::font::{Font, FontId, GlyphId};
+use super::layout::Paragraph;
+use super::outline::Segment;
+use crate::math::aabb::Aabb2;
+use crate::math::vector::{Vec2, Vec4};
+use crate::render_pipeline;
+use crate::util::{as_u8_slice, as_u8_slice_from_slice};
+use std::collections::HashMap;
+use std::ops::Range;
+
+/// A glyph instance, the `Glyph` struct in `font_renderer.wgsl`.
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+struct Instance {
+    bounds: Aabb2,
+    color: Vec4,
+    position: Vec2,
+    size: f32,
+    first_segment: u32,
+    contours: [u32; 2],
+    _padding: [u32; 2],
+}
+
+/// The `View` uniform in `font_renderer.wgsl`.
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+struct View {
+    scale: Vec2,
+    offset: Vec2,
+    zoom: f32,
+    render_percent: f32,
+}
+
+/// Where a glyph's outline is in the segment buffers.
+#[derive(Debug, Clone)]
+struct Uploaded {
+    bounds: Aabb2,
+    first_segment: u32,
+    contours: Range<u32>,
+}
+
+/// Draws text from its outlines with `font_renderer.wgsl`, so it is sharp at
+/// any zoom without an atlas. Every glyph is a quad around its bounds that
+/// measures the distance to the outline's segments per pixel, which is slower
+/// than sampling an atlas for lots of small text.
+pub struct VectorTextRenderer {
+    pipeline: wgpu::RenderPipeline,
+    segment_buffer: wgpu::Buffer,
+    contour_buffer: wgpu::Buffer,
+    view_buffer: wgpu::Buffer,
+    glyph_buffer: wgpu::Buffer,
+    bind_group: wgpu::BindGroup,
+    segments: Vec<Segment>,
+    contour_markers: Vec<u32>,
+    /// `None` for glyphs without an outline, like space.
+    outlines: HashMap<(FontId, GlyphId), Option<Uploaded>>,
+    instances: Vec<Instance>,
+    view: View,
+    /// Instances in the buffer, can be less than `instances` until `prepare` is called.
+    drawn: u32,
+    dirty: bool,
+    /// Outlines were added since the last `prepare`.
+    outlines_dirty: bool,
+}
+
+impl VectorTextRenderer {
+    /// How far flattened curves may be from the real ones in ems.
+    const TOLERANCE: f32 = 1.0 / 2048.0;
+    /// Elements the storage buffers have room for at first, bindings can not be empty.
+    const MIN_CAPACITY: u64 = 64;
+
+    pub fn new(device: &wgpu::Device, format: wgpu::TextureFormat) -> Self {
+        let module = &device.create_shader_module(wgpu::include_wgsl!("../font_renderer.wgsl"));
+
+        let pipeline = render_pipeline::Builder::new()
+            .add_device(device)
+            .add_label("vector text render pipeline")
+            .add_vertex(wgpu::VertexState {
+                module,
+                entry_point: Some("vs_main"),
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+                buffers: &[],
+            })
+            .add_primitive(wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleStrip,
+                ..Default::default()
+            })
+            .add_fragment(wgpu::FragmentState {
+                module,
+                entry_point: Some("fs_main"),
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format,
+                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            })
+            .build();
+
+        let segment_buffer = storage_buffer::<Segment>(device, "segment buffer", 0);
+        let contour_buffer = storage_buffer::<u32>(device, "contour marker buffer", 0);
+        let glyph_buffer = storage_buffer::<Instance>(device, "vector glyph buffer", 0);
+
+        let view_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("vector text view buffer"),
+            size: std::mem::size_of::<View>() as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let bind_group = create_bind_group(
+            device,
+            &pipeline,
+            [
+                &segment_buffer,
+                &contour_buffer,
+                &view_buffer,
+                &glyph_buffer,
+            ],
+        );
+
+        Self {
+            pipeline,
+            segment_buffer,
+            contour_buffer,
+            view_buffer,
+            glyph_buffer,
+            bind_group,
+            segments: Vec::new(),
+            contour_markers: Vec::new(),
+            outlines: HashMap::new(),
+            instances: Vec::new(),
+            view: View {
+                scale: Vec2::new(2.0, 2.0),
+                offset: Vec2::new(0.0, 0.0),
+                zoom: 1.0,
+                render_percent: 1.0,
+            },
+            drawn: 0,
+            dirty: true,
+            outlines_dirty: false,
+        }
+    }
+
+    /// The size of the target in pixels, text is placed from its top left corner.
+    #[allow(clippy::cast_precision_loss)]
+    pub fn set_resolution(&mut self, width: u32, height: u32) {
+        self.view.scale = Vec2::new(2.0 / width as f32, 2.0 / height as f32);
+        self.dirty = true;
+    }
+
+    /// Scales the text around the top left corner and then moves it by `offset` pixels.
+    /// Outlines are not flattened again so zooming is free.
+    pub fn set_view(&mut self, offset: Vec2, zoom: f32) {
+        self.view.offset = offset;
+        self.view.zoom = zoom;
+        self.dirty = true;
+    }
+
+    /// Draws only the first part of each glyph's outline, 1 draws all of it.
+    pub fn set_render_percent(&mut self, render_percent: f32) {
+        self.view.render_percent = render_percent.clamp(0.0, 1.0);
+        self.dirty = true;
+    }
+
+    /// Adds a glyph with its pen position at `position` pixels, y up from the top of the target.
+    pub fn queue_glyph(
+        &mut self,
+        font: &Font,
+        glyph: GlyphId,
+        font_size: f32,
+        position: Vec2,
+        color: Vec4,
+    ) {
+        let Some(outline) = self.outline(font, glyph) else {
+            return;
+        };
+
+        self.instances.push(Instance {
+            bounds: outline.bounds,
+            color,
+            position,
+            size: font_size,
+            first_segment: outline.first_segment,
+            contours: [outline.contours.start, outline.contours.end],
+            _padding: [0; 2],
+        });
+        self.dirty = true;
+    }
+
+    /// Adds every glyph of `paragraph` with its top left corner at `origin`.
+    /// Underlines and strikethroughs are not drawn.
+    pub fn queue_paragraph(&mut self, paragraph: &Paragraph, origin: Vec2) {
+        for glyph in paragraph.glyphs() {
+            let (_, style) = &paragraph.spans()[glyph.span];
+
+            self.queue_glyph(
+                &style.font,
+                glyph.glyph,
+                style.font_size,
+                origin + glyph.position,
+                super::shader_color(style.color, style.alpha),
+            );
+        }
+    }
+
+    /// Removes the queued glyphs, their outlines stay uploaded for next time.
+    pub fn clear(&mut self) {
+        self.instances.clear();
+        self.dirty = true;
+    }
+
+    /// Writes new outlines, the queued glyphs and the view to their buffers,
+    /// growing the buffers when they do not fit.
+    pub fn prepare(&mut self, device: &wgpu::Device, queue: &wgpu::Queue) {
+        if !self.dirty {
+            return;
+        }
+
+        let mut grown = false;
+
+        if self.outlines_dirty {
+            grown |= write_storage(
+                device,
+                queue,
+                &mut self.segment_buffer,
+                "segment buffer",
+                &self.segments,
+            );
+            grown |= write_storage(
+                device,
+                queue,
+                &mut self.contour_buffer,
+                "contour marker buffer",
+                &self.contour_markers,
+            );
+            self.outlines_dirty = false;
+        }
+
+        grown |= write_storage(
+            device,
+            queue,
+            &mut self.glyph_buffer,
+            "vector glyph buffer",
+            &self.instances,
+        );
+
+        if grown {
+            self.bind_group = create_bind_group(
+                device,
+                &self.pipeline,
+                [
+                    &self.segment_buffer,
+                    &self.contour_buffer,
+                    &self.view_buffer,
+                    &self.glyph_buffer,
+                ],
+            );
+        }
+
+        queue.write_buffer(&self.view_buffer, 0, unsafe { as_u8_slice(&self.view) });
+
+        self.drawn = u32::try_from(self.instances.len()).expect("fewer than 4 billion glyphs");
+        self.dirty = false;
+    }
+
+    /// Draws what was queued at the last `prepare`.
+    pub fn render(&self, pass: &mut wgpu::RenderPass) {
+        if self.drawn == 0 {
+            return;
+        }
+
+        pass.set_pipeline(&self.pipeline);
+        pass.set_bind_group(0, &self.bind_group, &[]);
+        pass.draw(0..4, 0..self.drawn);
+    }
+
+    /// Flattens a glyph into the segment buffers the first time it is used.
+    fn outline(&mut self, font: &Font, glyph: GlyphId) -> Option<Uploaded> {
+        let segments = &mut self.segments;
+        let contour_markers = &mut self.contour_markers;
+        let outlines_dirty = &mut self.outlines_dirty;
+
+        self.outlines
+            .entry((font.id(), glyph))
+            .or_insert_with(|| {
+                let outline = font.outline(glyph, 1.0 / font.units_per_em(), Self::TOLERANCE)?;
+
+                if outline.contour_markers.is_empty() {
+                    return None;
+                }
+
+                let first_segment = u32::try_from(segments.len()).ok()?;
+                let first_contour = u32::try_from(contour_markers.len()).ok()?;
+
+                *outlines_dirty = true;
+                segments.extend(outline.segments());
+                contour_markers.extend(
+                    outline
+                        .contour_markers
+                        .iter()
+                        .map(|&end| first_segment + end),
+                );
+
+                Some(Uploaded {
+                    bounds: outline.bounds,
+                    first_segment,
+                    contours: first_contour..u32::try_from(contour_markers.len()).ok()?,
+                })
+            })
+            .clone()
+    }
+}
+
+fn storage_buffer<T>(device: &wgpu::Device, label: &str, len: usize) -> wgpu::Buffer {
+    let capacity = (len as u64)
+        .next_power_of_two()
+        .max(VectorTextRenderer::MIN_CAPACITY);
+
+    device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some(label),
+        size: capacity * std::mem::size_of::<T>() as u64,
+        usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    })
+}
+
+/// Writes `items` to `buffer`, making a bigger one first if they do not fit.
+/// Returns `true` when the buffer was replaced and bind groups using it are stale.
+fn write_storage<T: Copy>(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    buffer: &mut wgpu::Buffer,
+    label: &str,
+    items: &[T],
+) -> bool {
+    let bytes = unsafe { as_u8_slice_from_slice(items) };
+    let grown = bytes.len() as u64 > buffer.size();
+
+    if grown {
+        *buffer = storage_buffer::<T>(device, label, items.len());
+    }
+
+    queue.write_buffer(buffer, 0, bytes);
+
+    grown
+}
+
+fn create_bind_group(
+    device: &wgpu::Device,
+    pipeline: &wgpu::RenderPipeline,
+    buffers: [&wgpu::Buffer; 4],
+) -> wgpu::BindGroup {
+    let entries: Vec<_> = (0..)
+        .zip(buffers)
+        .map(|(binding, buffer)| wgpu::BindGroupEntry {
+            binding,
+            resource: buffer.as_entire_binding(),
+        })
+        .collect();
+
+    device.create_bind_group(&wgpu::BindGroupDescriptor {
+        label: Some("vector text bind group"),
+        layout: &pipeline.get_bind_group_layout(0),
+        entries: &entries,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[allow(
+        clippy::cast_possible_truncation,
+        clippy::cast_sign_loss,
+        clippy::cast_precision_loss
+    )]
+    fn renders_a_glyph() {
+        const SIZE: u32 = 64;
+        const FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8Unorm;
+
+        let Ok((_instance, device, queue)) = crate::util::get_gpu() else {
+            eprintln!("no gpu adapter, skipping");
+            return;
+        };
+
+        let font = super::super::test_font();
+        let glyph = font.glyph_id('H').expect("the font has an H");
+        let font_size = 40.0;
+        // baseline 50 pixels down from the top, y is up
+        let position = Vec2::new(8.0, -50.0);
+
+        let mut renderer = VectorTextRenderer::new(&device, FORMAT);
+        renderer.set_resolution(SIZE, SIZE);
+        renderer.queue_glyph(
+            &font,
+            glyph,
+            font_size,
+            position,
+            Vec4::new(1.0, 1.0, 1.0, 1.0),
+        );
+        renderer.prepare(&device, &queue);
+
+        let target = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("vector text test target"),
+            size: wgpu::Extent3d {
+                width: SIZE,
+                height: SIZE,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: FORMAT,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[],
+        });
+
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+        {
+            let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: &target.create_view(&wgpu::TextureViewDescriptor::default()),
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                ..Default::default()
+            });
+            renderer.render(&mut pass);
+        }
+        queue.submit([encoder.finish()]);
+
+        let alpha: Vec<Vec<u8>> = crate::util::read_texture(&device, &queue, &target)
+            .iter()
+            .map(|row| row.chunks(4).map(|pixel| pixel[3]).collect())
+            .collect();
+
+        // the glyph's bounds in pixels with a pixel of antialiasing around them
+        let bounds = font.glyph_bounds(glyph).expect("H has an outline");
+        let scale = font_size / font.units_per_em();
+        let left = position.x + bounds.min.x * scale - 1.0;
+        let right = position.x + bounds.max.x * scale + 1.0;
+        let top = -position.y - bounds.max.y * scale - 1.0;
+        let bottom = -position.y - bounds.min.y * scale + 1.0;
+
+        let inside = |x: usize, y: usize| {
+            (left..right).contains(&(x as f32)) && (top..bottom).contains(&(y as f32))
+        };
+
+        for (y, row) in alpha.iter().enumerate() {
+            for (x, &a) in row.iter().enumerate() {
+                if !inside(x, y) {
+                    assert_eq!(a, 0, "pixel {x}, {y} is outside the glyph");
+                }
+            }
+        }
+
+        // the crossbar is solid from stem to stem, above it there is a gap between them
+        let crossbar = &alpha[f32::midpoint(top, bottom) as usize];
+        let first = crossbar
+            .iter()
+            .position(|&a| a == 255)
+            .expect("the crossbar is covered");
+        let last = crossbar
+            .iter()
+            .rposition(|&a| a == 255)
+            .expect("the crossbar is covered");
+
+        assert!(last - first > 20, "the crossbar spans {first} to {last}");
+        assert!(crossbar[first..=last].iter().all(|&a| a == 255));
+        assert_eq!(alpha[top as usize + 4][usize::midpoint(first, last)], 0);
+    }
+}
@@ -65,3 +65,54 @@ pub fn get_gpu() -> Result<(wgpu::Instance, wgpu::Device, wgpu::Queue), GpuError
 
     Ok((instance, device, queue))
 }
+
+/// Copies `texture` back to the cpu for tests, one `Vec` per row without the padding.
+#[cfg(test)]
+pub fn read_texture(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    texture: &wgpu::Texture,
+) -> Vec<Vec<u8>> {
+    let row = texture.width()
+        * texture
+            .format()
+            .block_copy_size(None)
+            .expect("the texture has one aspect");
+    // buffer copies need rows aligned to 256 bytes
+    let padded = row.next_multiple_of(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT);
+
+    let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("readback buffer"),
+        size: u64::from(padded * texture.height()),
+        usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+        mapped_at_creation: false,
+    });
+
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+    encoder.copy_texture_to_buffer(
+        texture.as_image_copy(),
+        wgpu::TexelCopyBufferInfo {
+            buffer: &buffer,
+            layout: wgpu::TexelCopyBufferLayout {
+                offset: 0,
+                bytes_per_row: Some(padded),
+                rows_per_image: Some(texture.height()),
+            },
+        },
+        texture.size(),
+    );
+    queue.submit([encoder.finish()]);
+
+    buffer.slice(..).map_async(wgpu::MapMode::Read, |result| {
+        result.expect("readback buffer maps");
+    });
+    device.poll(wgpu::Maintain::Wait);
+
+    let rows = buffer
+        .slice(..)
+        .get_mapped_range()
+        .chunks(padded as usize)
+        .map(|padded_row| padded_row[..row as usize].to_vec())
+        .collect();
+    rows
+}
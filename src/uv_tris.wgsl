@@ -2,6 +2,11 @@
 @group(0) @binding(1) var tex_sampler: sampler;
 @group(0) @binding(2) var<storage, read> quads: array<Quad>;
 
+// 0 for R8 atlases, 3 for RGBA8 ones that keep the glyph in alpha
+override glyph_channel: u32 = 3u;
+// false for atlases that store coverage rather than a distance field
+override distance_field: bool = true;
+
 struct VertexIn {
 	@location(0) position: vec2<f32>,
 	@location(1) uv: vec2<f32>,
@@ -10,11 +15,13 @@ struct VertexIn {
 struct Quad {
 	rect: vec4<f32>,
 	uv_rect: vec4<f32>,
+	color: vec4<f32>,
 }
 
 struct VertexOut {
 	@builtin(position) clip_position: vec4<f32>,
 	@location(0) uv: vec2<f32>,
+	@location(1) color: vec4<f32>,
 }
 
 struct FragmentOut {
@@ -87,12 +94,24 @@ fn vs_main(
 	return VertexOut(
 		vec4(pos[vertex], 0.0, 1.0),
 		vec2(uv[vertex]),
+		quad.color,
 	);
 }
 
 @fragment
 fn fs_main(vertex: VertexOut) -> FragmentOut {
+	let value = textureSampleBicubic(texture, tex_sampler, vertex.uv)[glyph_channel];
+
+	// a distance field has the edge at 0.5, antialias over a pixel
+	let width = fwidth(value) * 0.5;
+	var coverage = select(value, smoothstep(0.5 - width, 0.5 + width, value), distance_field);
+
+	// negative uvs mark solid quads like underlines
+	if (vertex.uv.x < 0.0) {
+		coverage = 1.0;
+	}
+
 	return FragmentOut(
-		textureSampleBicubic(texture, tex_sampler, vertex.uv) * vec4(0.0,0.0,0.0,1.0) + vec4(0.0,0.0,0.0,0.5),
+		vec4(vertex.color.rgb, vertex.color.a * coverage),
 	);
 }
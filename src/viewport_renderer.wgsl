@@ -20,9 +20,11 @@ fn vs_main(
 		vec2(1.0, 0.0),
 	)[in_vertex_index];
 
-	out.clip_position = vec4<f32>((uv + rectangle.xy) * rectangle.zw * 2.0 + vec2(-1.0, -1.0), vec2(0.0, 1.0));
+	// the rectangle is x, y, width, height as fractions of the target from the bottom left
+	out.clip_position = vec4<f32>((rectangle.xy + uv * rectangle.zw) * 2.0 + vec2(-1.0, -1.0), vec2(0.0, 1.0));
 
-	out.uv = uv;
+	// texture rows go down from the top while the rectangle goes up from the bottom
+	out.uv = vec2(uv.x, 1.0 - uv.y);
 
 	return out;
 }